@@ -3,6 +3,14 @@ pragma solidity ^0.8.20;
 
 // Path: contracts/RepositoryContract.sol
 contract RepositoryContract {
+    // each role may do everything the ones below it may
+    enum Role {
+        None,
+        Writer,
+        Maintainer,
+        Owner
+    }
+
     struct Object {
         string hash;
 
@@ -19,19 +27,231 @@ contract RepositoryContract {
         address pusher;
     }
 
+    // rules of a ref beyond write access
+    struct Protection {
+        bool isProtected;
+        // only these may update the ref, any writer when empty
+        address[] pushers;
+        // only refuses forceAddRefs: fast-forwards can't be checked
+        // on-chain, so a pusher may still rewrite the ref with addRefs.
+        // Clients declare their non-fast-forward updates, keep hostile
+        // writers out with pushers
+        bool allowForce;
+        bool allowDeletion;
+    }
+
     mapping(string => Object) public objects;
     mapping(string => Ref) public refs;
 
     Object[] public objectsById;
     Ref[] public refsById;
 
+    // every ref name ever written, to read the current refs without
+    // replaying refsById
+    string[] private refNames;
+    mapping(string => bool) private knownRefs;
+
+    // when each entry of refsById was made, for the history of refs
+    uint256[] private refBlocks;
+    uint256[] private refTimestamps;
+
     bytes public config;
 
+    mapping(address => Role) public roles;
+    address[] public members;
+    mapping(address => bool) private listed;
+    uint256 public ownerCount;
+
+    mapping(string => Protection) private protections;
+    string[] private protectedRefs;
+
     event ObjectSaved(string hash, bytes ipfs_url, address pusher);
     event RefAdded(string ref, bytes ipfs_url, address pusher);
     event ConfigUpdated(bytes config);
+    event RoleGranted(address account, Role role, address sender);
+    event RoleRevoked(address account, address sender);
+    event RefDeleted(string ref, address pusher);
+    event RefProtected(string ref, address[] pushers, bool allowForce, bool allowDeletion);
+    event RefUnprotected(string ref);
+
+    modifier onlyRole(Role _role) {
+        require(roles[msg.sender] >= _role, "RepositoryContract: caller lacks the required role");
+        _;
+    }
+
+    constructor() {
+        setRole(msg.sender, Role.Owner);
+
+        emit RoleGranted(msg.sender, Role.Owner, msg.sender);
+    }
+
+    function setRole(address _account, Role _role) private {
+        if (roles[_account] == Role.Owner) {
+            ownerCount--;
+        }
+
+        if (_role == Role.Owner) {
+            ownerCount++;
+        }
+
+        roles[_account] = _role;
+
+        if (!listed[_account]) {
+            listed[_account] = true;
+            members.push(_account);
+        }
+    }
+
+    // maintainers manage writers, owners manage everyone
+    function canManage(address _account, Role _role) private view returns (bool) {
+        if (roles[msg.sender] == Role.Owner) {
+            return true;
+        }
+
+        return _role < Role.Maintainer && roles[_account] < Role.Maintainer;
+    }
+
+    function grantRole(address _account, Role _role) public onlyRole(Role.Maintainer) {
+        require(_role != Role.None, "RepositoryContract: use revokeRole to remove a role");
+        require(canManage(_account, _role), "RepositoryContract: only owners manage maintainers and owners");
+        require(
+            roles[_account] != Role.Owner || _role == Role.Owner || ownerCount > 1,
+            "RepositoryContract: cannot demote the last owner"
+        );
+
+        setRole(_account, _role);
+
+        emit RoleGranted(_account, _role, msg.sender);
+    }
+
+    function revokeRole(address _account) public onlyRole(Role.Maintainer) {
+        require(roles[_account] != Role.None, "RepositoryContract: account has no role");
+        require(canManage(_account, Role.None), "RepositoryContract: only owners manage maintainers and owners");
+        require(
+            roles[_account] != Role.Owner || ownerCount > 1,
+            "RepositoryContract: cannot revoke the last owner"
+        );
+
+        setRole(_account, Role.None);
+
+        emit RoleRevoked(_account, msg.sender);
+    }
+
+    function getRole(address _account) public view returns (Role) {
+        return roles[_account];
+    }
+
+    function getMembers() public view returns (address[] memory, Role[] memory) {
+        uint256 count = 0;
+
+        for (uint256 i = 0; i < members.length; i++) {
+            if (roles[members[i]] != Role.None) {
+                count++;
+            }
+        }
+
+        address[] memory accounts = new address[](count);
+        Role[] memory accountRoles = new Role[](count);
+        uint256 j = 0;
+
+        for (uint256 i = 0; i < members.length; i++) {
+            if (roles[members[i]] != Role.None) {
+                accounts[j] = members[i];
+                accountRoles[j] = roles[members[i]];
+                j++;
+            }
+        }
+
+        return (accounts, accountRoles);
+    }
+
+    function protectRef(
+        string memory _ref,
+        address[] memory _pushers,
+        bool _allowForce,
+        bool _allowDeletion
+    ) public onlyRole(Role.Maintainer) {
+        if (!protections[_ref].isProtected) {
+            protectedRefs.push(_ref);
+        }
+
+        protections[_ref] = Protection(true, _pushers, _allowForce, _allowDeletion);
+
+        emit RefProtected(_ref, _pushers, _allowForce, _allowDeletion);
+    }
+
+    function unprotectRef(string memory _ref) public onlyRole(Role.Maintainer) {
+        require(protections[_ref].isProtected, "RepositoryContract: ref is not protected");
+
+        delete protections[_ref];
+
+        for (uint256 i = 0; i < protectedRefs.length; i++) {
+            if (keccak256(bytes(protectedRefs[i])) == keccak256(bytes(_ref))) {
+                protectedRefs[i] = protectedRefs[protectedRefs.length - 1];
+                protectedRefs.pop();
+                break;
+            }
+        }
+
+        emit RefUnprotected(_ref);
+    }
+
+    function getProtection(string memory _ref) public view returns (Protection memory) {
+        return protections[_ref];
+    }
+
+    function getProtectedRefs() public view returns (string[] memory) {
+        return protectedRefs;
+    }
+
+    function checkPusher(Protection storage _protection) private view {
+        if (_protection.pushers.length == 0) {
+            return;
+        }
+
+        for (uint256 i = 0; i < _protection.pushers.length; i++) {
+            if (_protection.pushers[i] == msg.sender) {
+                return;
+            }
+        }
+
+        revert("RepositoryContract: caller may not push to a protected ref");
+    }
+
+    function checkPush(string memory _ref, bool _force) private view {
+        Protection storage protection = protections[_ref];
+
+        if (!protection.isProtected) {
+            return;
+        }
+
+        checkPusher(protection);
+
+        require(!_force || protection.allowForce, "RepositoryContract: force-updates of a protected ref are forbidden");
+    }
+
+    // compare-and-swap: an update names the value it replaces, empty for a
+    // new ref, so it can't overwrite a push it didn't see
+    function checkCurrent(string memory _ref, bytes memory _old) private view {
+        Ref storage current = refs[_ref];
+        bytes memory value = current.is_active ? current.data : bytes("");
+
+        require(keccak256(value) == keccak256(_old), "RepositoryContract: ref was updated since it was read");
+    }
+
+    function checkDelete(string memory _ref) private view {
+        Protection storage protection = protections[_ref];
+
+        if (!protection.isProtected) {
+            return;
+        }
+
+        checkPusher(protection);
+
+        require(protection.allowDeletion, "RepositoryContract: deleting a protected ref is forbidden");
+    }
 
-    function saveObject(string memory _hash, bytes memory _ipfs_url) public {
+    function saveObject(string memory _hash, bytes memory _ipfs_url) public onlyRole(Role.Writer) {
         if (objects[_hash].ipfs_url.length > 0) {
             return;
         }
@@ -44,16 +264,30 @@ contract RepositoryContract {
         emit ObjectSaved(_hash, _ipfs_url, msg.sender);
     }
 
-    function addRef(string memory _ref, bytes memory _data) public {
-        address pusher = msg.sender;
+    function addRef(string memory _ref, bytes memory _old, bytes memory _data) public onlyRole(Role.Writer) {
+        checkCurrent(_ref, _old);
+        checkPush(_ref, false);
+
+        setRef(_ref, _data, true);
+
+        emit RefAdded(_ref, _data, msg.sender);
+    }
+
+    function setRef(string memory _ref, bytes memory _data, bool _active) private {
+        if (!knownRefs[_ref]) {
+            knownRefs[_ref] = true;
+            refNames.push(_ref);
+        }
 
-        refsById.push(Ref(_ref, _data, true, pusher));
-        refs[_ref] = Ref(_ref, _data, true, pusher);
+        Ref memory ref = Ref(_ref, _data, _active, msg.sender);
 
-        emit RefAdded(_ref, _data, pusher);
+        refsById.push(ref);
+        refBlocks.push(block.number);
+        refTimestamps.push(block.timestamp);
+        refs[_ref] = ref;
     }
 
-    function updateConfig(bytes memory _config) public {
+    function updateConfig(bytes memory _config) public onlyRole(Role.Maintainer) {
         config = _config;
 
         emit ConfigUpdated(_config);
@@ -85,7 +319,17 @@ contract RepositoryContract {
         return results;
     }
 
-    function addObjects(string[] memory _hashes, bytes[] memory _ipfs_urls) public {
+    function getObjectsByHash(string[] memory _hashes) public view returns (Object[] memory) {
+        Object[] memory result = new Object[](_hashes.length);
+
+        for (uint256 i = 0; i < _hashes.length; i++) {
+            result[i] = objects[_hashes[i]];
+        }
+
+        return result;
+    }
+
+    function addObjects(string[] memory _hashes, bytes[] memory _ipfs_urls) public onlyRole(Role.Writer) {
         for (uint256 i = 0; i < _hashes.length; i++) {
             if (objects[_hashes[i]].ipfs_url.length > 0) {
                 continue;
@@ -100,13 +344,92 @@ contract RepositoryContract {
         }
     }
 
-    function addRefs(string[] memory _refs, bytes[] memory _data) public {
+    function addRefs(string[] memory _refs, bytes[] memory _old, bytes[] memory _data) public onlyRole(Role.Writer) {
+        setRefs(_refs, _old, _data, false);
+    }
+
+    function forceAddRefs(string[] memory _refs, bytes[] memory _old, bytes[] memory _data) public onlyRole(Role.Writer) {
+        setRefs(_refs, _old, _data, true);
+    }
+
+    function setRefs(string[] memory _refs, bytes[] memory _old, bytes[] memory _data, bool _force) private {
+        for (uint256 i = 0; i < _refs.length; i++) {
+            checkCurrent(_refs[i], _old[i]);
+            checkPush(_refs[i], _force);
+
+            setRef(_refs[i], _data[i], true);
+
+            emit RefAdded(_refs[i], _data[i], msg.sender);
+        }
+    }
+
+    // a deleted ref stays in the history as an inactive entry
+    function deleteRefs(string[] memory _refs, bytes[] memory _old) public onlyRole(Role.Writer) {
         for (uint256 i = 0; i < _refs.length; i++) {
-            address pusher = msg.sender;
-            refs[_refs[i]] = Ref(_refs[i], _data[i], true, pusher);
+            require(refs[_refs[i]].is_active, "RepositoryContract: ref does not exist");
+
+            checkCurrent(_refs[i], _old[i]);
+            checkDelete(_refs[i]);
+
+            setRef(_refs[i], "", false);
+
+            emit RefDeleted(_refs[i], msg.sender);
+        }
+    }
+
+    function getRef(string memory _ref) public view returns (Ref memory) {
+        return refs[_ref];
+    }
+
+    // the current value of every ref that wasn't deleted
+    function getActiveRefs() public view returns (Ref[] memory) {
+        uint256 count = 0;
+
+        for (uint256 i = 0; i < refNames.length; i++) {
+            if (refs[refNames[i]].is_active) {
+                count++;
+            }
+        }
+
+        Ref[] memory active = new Ref[](count);
+        uint256 j = 0;
+
+        for (uint256 i = 0; i < refNames.length; i++) {
+            if (refs[refNames[i]].is_active) {
+                active[j] = refs[refNames[i]];
+                j++;
+            }
+        }
+
+        return active;
+    }
+
+    // up to _count entries of refsById from _start, with the block number
+    // and timestamp they were made at
+    function getRefLog(uint256 _start, uint256 _count)
+        public
+        view
+        returns (Ref[] memory, uint256[] memory, uint256[] memory)
+    {
+        uint256 end = _start + _count;
+
+        if (end > refsById.length) {
+            end = refsById.length;
+        }
+
+        uint256 count = end > _start ? end - _start : 0;
 
-            emit RefAdded(_refs[i], _data[i], pusher);
+        Ref[] memory entries = new Ref[](count);
+        uint256[] memory blocks = new uint256[](count);
+        uint256[] memory timestamps = new uint256[](count);
+
+        for (uint256 i = 0; i < count; i++) {
+            entries[i] = refsById[_start + i];
+            blocks[i] = refBlocks[_start + i];
+            timestamps[i] = refTimestamps[_start + i];
         }
+
+        return (entries, blocks, timestamps);
     }
 
     function getRefs() public view returns (Ref[] memory) {
@@ -0,0 +1,29 @@
+use anyhow::bail;
+use dgit2::remote::Remote;
+use dgit2::remote_helper;
+
+// invoked by git as `git-remote-dgit <remote> [<url>]` for `dgit::` and
+// `dgit://` URLs
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv::dotenv().ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let Some(remote_name) = args.first() else {
+        bail!("Usage: git-remote-dgit <remote> [<url>]");
+    };
+
+    let url = match args.get(1) {
+        Some(url) => url.clone(),
+        None => Remote::get(remote_name)?.url(),
+    };
+
+    remote_helper::run(
+        remote_name,
+        &url,
+        std::io::stdin().lock(),
+        std::io::stdout().lock(),
+    )
+    .await
+}
@@ -7,30 +7,254 @@ pub struct Cli {
     pub command: Commands,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum RemoteCommands {
+    #[command(about = "Add a remote for a repository contract")]
+    Add {
+        /// The name of the remote.
+        name: String,
+        /// The contract address of the repository.
+        contract_address: String,
+        /// The RPC endpoint of the chain the contract is deployed on.
+        #[arg(long)]
+        rpc_url: Option<String>,
+        /// The storage backend used for objects pushed to this remote (pinata or ipfs).
+        #[arg(long)]
+        storage: Option<String>,
+    },
+
+    #[command(about = "Remove a remote and its remote-tracking refs", alias = "rm")]
+    Remove {
+        /// The name of the remote.
+        name: String,
+    },
+
+    #[command(about = "Rename a remote and its remote-tracking refs")]
+    Rename {
+        /// The current name of the remote.
+        old: String,
+        /// The new name of the remote.
+        new: String,
+    },
+
+    #[command(about = "List the remotes")]
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AccountCommands {
+    #[command(about = "List the accounts of the keystore directory")]
+    List,
+
+    #[command(about = "Create an account in a new encrypted keystore")]
+    New,
+
+    #[command(about = "Import a private key, mnemonic or keystore into the keystore directory")]
+    Import {
+        /// Derive the key from a BIP-39 mnemonic instead of a raw private key.
+        #[arg(long, conflicts_with = "keystore")]
+        mnemonic: bool,
+        /// The derivation path of the key in the mnemonic.
+        #[arg(long, requires = "mnemonic", default_value = crate::config::DEFAULT_DERIVATION_PATH)]
+        derivation_path: String,
+        /// Import an existing JSON keystore file.
+        #[arg(long)]
+        keystore: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AccessCommands {
+    #[command(about = "List the addresses that have a role on the repository contract")]
+    List,
+
+    #[command(about = "Give an address a role on the repository contract")]
+    Grant {
+        /// The address to grant the role to.
+        address: String,
+        /// The role: writer, maintainer or owner.
+        role: String,
+    },
+
+    #[command(about = "Take away the role of an address on the repository contract")]
+    Revoke {
+        /// The address to revoke the role of.
+        address: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BundleCommands {
+    #[command(about = "Write the given refs and their history to a git bundle")]
+    Create {
+        /// The bundle file to write.
+        file: String,
+        /// The refs to include, all branches and tags by default.
+        refs: Vec<String>,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 #[clap(about = "A distributed Git")]
 pub enum Commands {
     #[command(about = "Initialize a repository")]
     Init {
-        /// The contract address of the repository.
-        #[arg(short, long)]
+        /// The contract address of the repository, added as the `origin` remote.
+        #[arg(short, long, conflicts_with = "deploy")]
         contract_address: Option<String>,
         /// Deploy the repository contract.
         #[arg(short, long)]
         deploy: bool,
+        /// The branch HEAD points at in the new repository.
+        #[arg(short = 'b', long)]
+        initial_branch: Option<String>,
     },
 
     #[command(about = "Clone a repository")]
     Clone {
         /// The URL of the repository to clone.
         contract_address: String,
+        /// The directory to clone into, the current directory by default.
+        dir: Option<String>,
+        /// Create a bare repository, without a working tree.
+        #[arg(long, conflicts_with = "no_checkout")]
+        bare: bool,
+        /// Check out this branch instead of the remote's default branch.
+        #[arg(short, long)]
+        branch: Option<String>,
+        /// Don't check out HEAD after the clone.
+        #[arg(short, long)]
+        no_checkout: bool,
+        /// Create a shallow clone with only the last <depth> commits.
+        #[arg(long)]
+        depth: Option<usize>,
+        /// Only fetch the history of the branch that is checked out.
+        #[arg(long)]
+        single_branch: bool,
+        /// Leave blobs out of the clone until they are needed (blob:none or blob:limit=<size>).
+        #[arg(long)]
+        filter: Option<String>,
+    },
+
+    #[command(about = "Import an existing git repository into a repository contract")]
+    Import {
+        /// The git repository to import (a work tree or a bare repository).
+        path: String,
+        /// Import into this contract instead of deploying a new one.
+        #[arg(short, long)]
+        contract_address: Option<String>,
+        /// Skip downloading the imported objects back to verify them.
+        #[arg(long)]
+        no_verify: bool,
+    },
+
+    #[command(about = "Export a repository contract to a bare git repository")]
+    Export {
+        /// The directory of the bare repository to create.
+        dir: String,
+        /// Create a bare repository (required).
+        #[arg(long)]
+        bare: bool,
+        /// Export this contract instead of the default remote.
+        #[arg(short, long)]
+        contract_address: Option<String>,
+    },
+
+    #[command(about = "Create git bundles of the repository")]
+    Bundle {
+        #[command(subcommand)]
+        command: BundleCommands,
     },
 
     #[command(about = "Sync the repository")]
-    Pull,
+    Pull {
+        /// The remote to pull from, defaults to the upstream of the current branch or origin.
+        remote: Option<String>,
+        /// The remote branch to integrate into the current branch.
+        refspecs: Vec<String>,
+    },
 
     #[command(about = "Push the repository")]
-    Push,
+    Push {
+        /// The remote to push to, defaults to the upstream of the current branch or origin.
+        remote: Option<String>,
+        /// The refs to push, as `[+]<src>[:<dst>]`.
+        refspecs: Vec<String>,
+        /// Estimate the gas and cost of the push without sending anything.
+        #[arg(short = 'n', long, conflicts_with = "status")]
+        dry_run: bool,
+        /// Show the uploads and transactions an interrupted push left pending.
+        #[arg(long)]
+        status: bool,
+        /// Delete the given refs from the remote instead of pushing them.
+        #[arg(short, long, conflicts_with = "status")]
+        delete: bool,
+    },
+
+    #[command(about = "Download objects and refs from a remote repository")]
+    Fetch {
+        /// The remote to fetch from, defaults to the upstream of the current branch or origin.
+        remote: Option<String>,
+        /// The refs to fetch, as `[+]<src>[:<dst>]`.
+        refspecs: Vec<String>,
+        /// Extend the history of a shallow repository by <deepen> commits.
+        #[arg(long, conflicts_with = "unshallow")]
+        deepen: Option<usize>,
+        /// Download the complete history of a shallow repository.
+        #[arg(long)]
+        unshallow: bool,
+        /// Delete remote-tracking refs whose ref was deleted on the remote.
+        #[arg(short, long)]
+        prune: bool,
+    },
+
+    #[command(about = "Manage the set of tracked repository contracts")]
+    Remote {
+        /// Show the contract address and settings of each remote.
+        #[arg(short, long)]
+        verbose: bool,
+        #[command(subcommand)]
+        command: Option<RemoteCommands>,
+    },
+
+    #[command(about = "Manage the Ethereum accounts transactions are signed with")]
+    Account {
+        #[command(subcommand)]
+        command: AccountCommands,
+    },
+
+    #[command(about = "Manage who may write to the repository contract")]
+    Access {
+        /// The remote whose contract to manage, the default remote if not given.
+        #[arg(short, long)]
+        remote: Option<String>,
+        #[command(subcommand)]
+        command: AccessCommands,
+    },
+
+    #[command(about = "Protect refs of the repository contract, or list the protected refs")]
+    Protect {
+        /// The ref to protect, like main or refs/heads/main. Lists the
+        /// protected refs when not given.
+        name: Option<String>,
+        /// An address that may update the ref, any writer may when none is given.
+        #[arg(long = "pusher", value_name = "ADDRESS")]
+        pushers: Vec<String>,
+        /// Accept updates sent as force-pushes. The contract can't tell
+        /// fast-forwards apart, so without it a pusher can still rewrite the
+        /// ref: use --pusher to limit who may.
+        #[arg(long)]
+        allow_force: bool,
+        /// Accept the deletion of the ref.
+        #[arg(long)]
+        allow_deletion: bool,
+        /// Lift the protection of the ref instead.
+        #[arg(long, requires = "name", conflicts_with_all = ["pushers", "allow_force", "allow_deletion"])]
+        remove: bool,
+        /// The remote whose contract to manage, the default remote if not given.
+        #[arg(short, long)]
+        remote: Option<String>,
+    },
 
     #[command(about = "Get the status of the repository")]
     Status,
@@ -60,9 +284,26 @@ pub enum Commands {
 
     #[command(about = "Provide contents or details of repository objects")]
     CatFile {
+        /// Instead of the content, show the object type identified by <object>.
+        #[arg(short = 't', group = "cat_file_mode")]
+        show_type: bool,
+        /// Instead of the content, show the object size identified by <object>.
+        #[arg(short = 's', group = "cat_file_mode")]
+        show_size: bool,
+        /// Exit with zero status if <object> exists and is a valid object.
+        #[arg(short = 'e', group = "cat_file_mode")]
+        exists: bool,
         /// Pretty-print the contents of <object> based on its type.
-        #[arg(short, long)]
-        pretty_print: String,
+        #[arg(short = 'p', group = "cat_file_mode")]
+        pretty_print: bool,
+        /// Print object information and contents for each object read from stdin.
+        #[arg(long, group = "cat_file_mode")]
+        batch: bool,
+        /// Print object information for each object read from stdin.
+        #[arg(long, group = "cat_file_mode")]
+        batch_check: bool,
+        /// The name of the object to show.
+        object: Option<String>,
     },
 
     #[command(about = "Create a tree object from the current index")]
@@ -73,6 +314,58 @@ pub enum Commands {
         /// The commit message.
         #[arg(short, long)]
         message: Option<String>,
+        /// Override the commit author, in the form `Name <email>`.
+        #[arg(long)]
+        author: Option<String>,
+        /// Override the author date.
+        #[arg(long)]
+        date: Option<String>,
+        /// Use the Ethereum account that will push as the commit identity.
+        #[arg(long)]
+        eth_identity: bool,
+        /// Sign the commit with the Ethereum key it will be pushed with.
+        #[arg(short = 'S', long = "gpg-sign")]
+        sign: bool,
+    },
+
+    #[command(about = "Show the commit history")]
+    Log {
+        /// The commit to start from, HEAD by default.
+        revision: Option<String>,
+        /// Limit the number of commits to show.
+        #[arg(short = 'n', long)]
+        max_count: Option<usize>,
+        /// Check the signature of each commit against the account that pushed it.
+        #[arg(long)]
+        show_signature: bool,
+    },
+
+    #[command(
+        about = "Show the history of a ref, or of the refs of a remote: who updated them and when"
+    )]
+    Reflog {
+        /// The ref whose history to show, HEAD by default, or every ref of a remote.
+        name: Option<String>,
+        /// Show the history kept by the contract of a remote instead, the default
+        /// remote when no name is given.
+        #[arg(long, num_args = 0..=1, default_missing_value = "", value_name = "REMOTE")]
+        remote: Option<String>,
+        /// Limit the number of entries to show.
+        #[arg(short = 'n', long)]
+        max_count: Option<usize>,
+        /// Only show the updates made by this address on the remote.
+        #[arg(long, value_name = "ADDRESS", requires = "remote")]
+        pusher: Option<String>,
+        /// Print the entries as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    #[command(about = "Check the signatures of commits against the accounts that pushed them")]
+    VerifyCommit {
+        /// The commits to verify.
+        #[arg(required = true)]
+        commits: Vec<String>,
     },
 
     #[command(about = "Compute object ID and optionally create an object from a file")]
@@ -84,10 +377,72 @@ pub enum Commands {
 
     #[command(about = "List the contents of a tree object")]
     LsTree {
-        /// The object to list the contents of.
-        path: String,
-        /// List only filenames (instead of the "long" output), one per line. Cannot be combined with --object-only.
+        /// The tree-ish (commit, tree or ref) to list the contents of.
+        tree_ish: String,
+        /// Only show the entries matching these paths.
+        paths: Vec<String>,
+        /// Recurse into sub-trees.
         #[arg(short, long)]
+        recursive: bool,
+        /// Show tree entries even when going to recurse them.
+        #[arg(short = 't')]
+        show_trees: bool,
+        /// Show only the named tree entries themselves, not their children.
+        #[arg(short = 'd')]
+        only_trees: bool,
+        /// Show the object size of blob entries.
+        #[arg(short, long)]
+        long: bool,
+        /// List only filenames (instead of the "long" output), one per line.
+        #[arg(long)]
         name_only: bool,
     },
+
+    #[command(about = "Show information about files in the index and the working tree")]
+    LsFiles {
+        /// Show tracked files in the output (the default).
+        #[arg(short, long)]
+        cached: bool,
+        /// Show untracked files in the output.
+        #[arg(short, long)]
+        others: bool,
+        /// Show only ignored files in the output.
+        #[arg(short, long)]
+        ignored: bool,
+        /// Show mode bits, object name and stage number of tracked files.
+        #[arg(short, long)]
+        stage: bool,
+    },
+
+    #[command(about = "Get and set repository or global options")]
+    Config {
+        /// Use the global config file (~/.gitconfig).
+        #[arg(long, group = "config_scope")]
+        global: bool,
+        /// Use the system config file.
+        #[arg(long, group = "config_scope")]
+        system: bool,
+        /// Use the repository config file (.git/config).
+        #[arg(long, group = "config_scope")]
+        local: bool,
+        /// Get the value for a given key.
+        #[arg(long, value_name = "KEY", group = "config_action")]
+        get: Option<String>,
+        /// Set the value for a given key, replacing existing values.
+        #[arg(long, num_args = 2, value_names = ["KEY", "VALUE"], group = "config_action")]
+        set: Option<Vec<String>>,
+        /// Add a new value to a multi-valued key.
+        #[arg(long, num_args = 2, value_names = ["KEY", "VALUE"], group = "config_action")]
+        add: Option<Vec<String>>,
+        /// Remove all values of a key.
+        #[arg(long, value_name = "KEY", group = "config_action")]
+        unset: Option<String>,
+        /// List all variables set in the config files.
+        #[arg(short, long, group = "config_action")]
+        list: bool,
+        /// The key to get, or to set when followed by a value.
+        key: Option<String>,
+        /// The value to set.
+        value: Option<String>,
+    },
 }
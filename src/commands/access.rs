@@ -0,0 +1,98 @@
+use crate::commands::{print_costs, resolve_remote};
+use crate::contract_interaction::{address_to_string, parse_address, ContractInteraction, Role};
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+#[derive(Debug, Clone)]
+pub enum AccessAction {
+    /// List the addresses that have a role.
+    List,
+    /// Give an address a role, replacing the one it had.
+    Grant { address: String, role: Role },
+    /// Take away the role of an address.
+    Revoke { address: String },
+}
+
+// maintainers manage writers, owners manage everyone
+fn managed_by(role: Role) -> Role {
+    if role <= Role::Writer {
+        Role::Maintainer
+    } else {
+        Role::Owner
+    }
+}
+
+/// Manage who may write to the repository contract of `remote`, or of the
+/// default remote.
+pub async fn access(remote: Option<String>, action: AccessAction) -> Result<()> {
+    let remote = resolve_remote(remote).await?;
+    let contract = ContractInteraction::for_remote(&remote)?;
+
+    match action {
+        AccessAction::List => {
+            let sender = contract.sender_address().await?;
+
+            for (address, role) in contract.get_members().await? {
+                if address == sender {
+                    println!(
+                        "* {:<10} {}",
+                        role.to_string().green(),
+                        address_to_string(&address)
+                    );
+                } else {
+                    println!("  {:<10} {}", role, address_to_string(&address));
+                }
+            }
+        }
+        AccessAction::Grant { address, role } => {
+            let account = parse_address(&address)?;
+            let current = contract.get_role(account).await?;
+
+            contract.require_role(managed_by(role.max(current))).await?;
+
+            let cost = contract.grant_role(account, role).await?;
+
+            println!(
+                "{}",
+                format!(
+                    "Granted {} to {} on {}",
+                    role,
+                    address_to_string(&account),
+                    remote.name
+                )
+                .green()
+            );
+            print_costs(&[cost]);
+        }
+        AccessAction::Revoke { address } => {
+            let account = parse_address(&address)?;
+            let role = contract.get_role(account).await?;
+
+            if role == Role::None {
+                bail!(
+                    "{} has no role on {}",
+                    address_to_string(&account),
+                    remote.name
+                );
+            }
+
+            contract.require_role(managed_by(role)).await?;
+
+            let cost = contract.revoke_role(account).await?;
+
+            println!(
+                "{}",
+                format!(
+                    "Revoked {} from {} on {}",
+                    role,
+                    address_to_string(&account),
+                    remote.name
+                )
+                .green()
+            );
+            print_costs(&[cost]);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use crate::config::Config;
+use crate::contract_interaction::address_to_string;
+use crate::signer::{
+    decrypt_keystore, encrypt_keystore, key_from_mnemonic, list_keystores, parse_private_key,
+    password,
+};
+use anyhow::{bail, Result};
+use colored::Colorize;
+use ethcontract::PrivateKey;
+use rand::RngCore;
+use std::path::Path;
+
+/// What an imported account is read from.
+#[derive(Debug, Clone)]
+pub enum AccountSource {
+    /// A raw hex private key, typed at a prompt.
+    PrivateKey,
+    /// A BIP-39 mnemonic typed at a prompt, and the path to derive at.
+    Mnemonic { derivation_path: String },
+    /// An existing keystore file, re-encrypted into the keystore directory.
+    Keystore(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum AccountAction {
+    List,
+    New,
+    Import { source: AccountSource },
+}
+
+// the password of a new keystore, typed twice
+fn new_password() -> Result<String> {
+    if let Some(password) = Config::keystore_password() {
+        return Ok(password);
+    }
+
+    let first = rpassword::prompt_password("Password for the new keystore: ")?;
+    let second = rpassword::prompt_password("Repeat the password: ")?;
+
+    if first != second {
+        bail!("Passwords do not match");
+    }
+
+    Ok(first)
+}
+
+fn random_key() -> PrivateKey {
+    let mut bytes = [0u8; 32];
+
+    // a random 32 byte string is outside the curve order with negligible
+    // probability
+    loop {
+        rand::thread_rng().fill_bytes(&mut bytes);
+
+        if let Ok(key) = PrivateKey::from_slice(bytes) {
+            return key;
+        }
+    }
+}
+
+fn save(key: &PrivateKey) -> Result<()> {
+    let path = encrypt_keystore(key, &new_password()?)?;
+
+    println!(
+        "{}",
+        format!("Account {}", address_to_string(&key.public_address())).green()
+    );
+    println!("Keystore saved to {}", path.display());
+    println!(
+        "Sign with it with `dgit config --global dgit.keystore {}`",
+        address_to_string(&key.public_address())
+    );
+
+    Ok(())
+}
+
+pub fn account(action: AccountAction) -> Result<()> {
+    match action {
+        AccountAction::List => {
+            let configured = Config::keystore()?;
+
+            for (address, path) in list_keystores()? {
+                let selected = configured.as_ref().is_some_and(|keystore| {
+                    keystore.to_lowercase() == address || Path::new(keystore) == path.as_path()
+                });
+
+                if selected {
+                    println!("* {}\t{}", address.green(), path.display());
+                } else {
+                    println!("  {}\t{}", address, path.display());
+                }
+            }
+        }
+        AccountAction::New => save(&random_key())?,
+        AccountAction::Import { source } => {
+            let key = match source {
+                AccountSource::PrivateKey => {
+                    parse_private_key(&rpassword::prompt_password("Private key: ")?)?
+                }
+                AccountSource::Mnemonic { derivation_path } => {
+                    key_from_mnemonic(&rpassword::prompt_password("Mnemonic: ")?, &derivation_path)?
+                }
+                AccountSource::Keystore(file) => {
+                    let path = Path::new(&file);
+
+                    decrypt_keystore(path, &password(&format!("Password for {}: ", file))?)?
+                }
+            };
+
+            save(&key)?;
+        }
+    }
+
+    Ok(())
+}
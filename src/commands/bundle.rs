@@ -0,0 +1,152 @@
+use crate::git::head::resolve_head;
+use crate::git::objects::commit::CommitObject;
+use crate::git::objects::header::{ObjectHeader, ObjectType};
+use crate::git::pack::write_pack;
+use crate::git::refs::Ref;
+use crate::git::repository::Repository;
+use crate::git::revision::find_ref;
+use crate::git::walk::{parse_children, reachable_objects};
+use anyhow::{bail, Result};
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::{BufWriter, Write};
+
+const BUNDLE_V2_SIGNATURE: &str = "# v2 git bundle\n";
+
+// the refs named on the command line, or every branch and tag
+async fn bundle_refs(refs: Vec<String>) -> Result<BTreeMap<String, String>> {
+    let mut result = BTreeMap::new();
+
+    if refs.is_empty() {
+        for (name, content) in Ref::get_all_refs().await? {
+            let value = Ref::parse_ref_content(&String::from_utf8_lossy(&content));
+
+            if (name.starts_with("refs/heads/") || name.starts_with("refs/tags/"))
+                && !value.starts_with("refs/")
+            {
+                result.insert(name, value);
+            }
+        }
+    }
+
+    for name in refs {
+        let (name, hash) = if name == "HEAD" {
+            (name, resolve_head().await?)
+        } else {
+            let Some(full_name) = find_ref(&name)? else {
+                bail!("Unknown ref: {}", name);
+            };
+
+            let hash = Ref::new(&full_name).resolve().await?;
+
+            (full_name, hash)
+        };
+
+        if hash.is_empty() {
+            bail!("{} does not point to a commit yet", name);
+        }
+
+        result.insert(name, hash);
+    }
+
+    if result.is_empty() {
+        bail!("Refusing to create an empty bundle");
+    }
+
+    Ok(result)
+}
+
+/// Write a git bundle (v2) of `refs` and everything reachable from them,
+/// which `git clone` and `git fetch` accept in place of a remote.
+pub async fn bundle_create(file: &str, refs: Vec<String>) -> Result<()> {
+    let repository = Repository::current()?;
+
+    let refs = bundle_refs(refs).await?;
+
+    for (name, hash) in &refs {
+        if !repository.has_object(hash) {
+            bail!("Object {} of {} is missing", hash, name);
+        }
+    }
+
+    let tips = refs.values().cloned().collect::<Vec<String>>();
+
+    let mut objects = Vec::new();
+    // the parents a shallow clone cut off: the bundle doesn't carry them,
+    // whoever fetches from it must have them already
+    let mut prerequisites = BTreeSet::new();
+
+    for hash in reachable_objects(&repository, &tips, &HashSet::new())? {
+        let data = repository.read_object(&hash)?;
+        let header = ObjectHeader::try_from(data.clone())?;
+
+        if header.object_type == ObjectType::Commit {
+            prerequisites.extend(
+                CommitObject::try_from(data.clone())?
+                    .content
+                    .parent_sha
+                    .into_iter()
+                    .filter(|parent| !repository.has_object(parent)),
+            );
+        }
+
+        // a bundle can only leave out whole commits, not the trees and
+        // blobs of a partial clone
+        for child in parse_children(data.clone())? {
+            if !repository.has_object(&child) && !prerequisites.contains(&child) {
+                bail!(
+                    "Object {} is missing, a partial clone can't be bundled: fetch it first",
+                    child
+                );
+            }
+        }
+
+        let content = data[header.header_size()..].to_vec();
+
+        objects.push((header.object_type, content));
+    }
+
+    let mut output = BufWriter::new(std::fs::File::create(file)?);
+
+    output.write_all(BUNDLE_V2_SIGNATURE.as_bytes())?;
+
+    for hash in &prerequisites {
+        writeln!(output, "-{}", hash)?;
+    }
+
+    for (name, hash) in &refs {
+        writeln!(output, "{} {}", hash, name)?;
+    }
+
+    writeln!(output)?;
+
+    write_pack(&objects, &mut output)?;
+
+    output.flush()?;
+
+    let commits = objects
+        .iter()
+        .filter(|(object_type, _)| *object_type == ObjectType::Commit)
+        .count();
+
+    println!(
+        "{}",
+        format!(
+            "Created bundle {} with {} refs, {} commits and {} objects",
+            file,
+            refs.len(),
+            commits,
+            objects.len()
+        )
+        .green()
+    );
+
+    if !prerequisites.is_empty() {
+        println!(
+            "The bundle requires {} commits missing from this shallow repository",
+            prerequisites.len()
+        );
+    }
+
+    Ok(())
+}
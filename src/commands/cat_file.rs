@@ -1,21 +1,144 @@
+use crate::error::Error;
 use crate::git::git_fs;
+use crate::git::objects::blob::BlobObject;
+use crate::git::objects::header::ObjectType;
+use crate::git::revision::resolve_revision;
 use anyhow::bail;
 use anyhow::Result;
+use std::io::Write;
 
-pub fn cat_file(path: &str) -> Result<()> {
-    // verify that the length of the path is sha1 length
-    if path.len() != 40 {
-        bail!("Invalid object path")
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CatFileMode {
+    /// Print the object type.
+    Type,
+    /// Print the object size.
+    Size,
+    /// Exit with zero status if the object exists, non-zero otherwise.
+    Exists,
+    /// Pretty-print the object content based on its type.
+    Pretty,
+    /// Read object names from stdin and print info and content for each.
+    Batch,
+    /// Read object names from stdin and print info for each.
+    BatchCheck,
+}
+
+pub async fn cat_file(mode: CatFileMode, object: Option<&str>) -> Result<()> {
+    match mode {
+        CatFileMode::Batch | CatFileMode::BatchCheck => {
+            return cat_file_batch(mode == CatFileMode::Batch).await
+        }
+        _ => {}
     }
 
-    let object = match git_fs::get_blob_object(path) {
-        Ok(object) => object,
-        Err(e) => {
-            bail!("Failed to get object: {}", e)
+    let Some(object) = object else {
+        bail!("Missing <object> argument");
+    };
+
+    let Some((hash, object)) = read_object(object).await? else {
+        if mode == CatFileMode::Exists {
+            return Err(Error::Exit(1).into());
         }
+
+        bail!("Not a valid object name {}", object);
+    };
+
+    match mode {
+        CatFileMode::Type => println!("{}", object.header.object_type),
+        CatFileMode::Size => println!("{}", object.header.size),
+        CatFileMode::Exists => {}
+        _ => pretty_print(&hash, &object.header.object_type, &object.data)?,
+    }
+
+    Ok(())
+}
+
+// the hash and parsed content of the object `name` names, read once: a
+// blob a partial clone left out is fetched by the read. `None` if there is
+// no such object
+async fn read_object(name: &str) -> Result<Option<(String, BlobObject)>> {
+    let Ok(hash) = resolve_revision(name).await else {
+        return Ok(None);
     };
 
-    print!("{}", std::str::from_utf8(&object.data)?);
+    match git_fs::get_raw_object(&hash) {
+        Ok(data) => {
+            let object = BlobObject::try_from(data).map_err(|e| Error::CorruptObject {
+                hash: hash.clone(),
+                reason: e.to_string(),
+            })?;
+
+            Ok(Some((hash, object)))
+        }
+        Err(Error::ObjectNotFound(_) | Error::InvalidObjectName(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn pretty_print(hash: &str, object_type: &ObjectType, data: &[u8]) -> Result<()> {
+    let mut stdout = std::io::stdout();
+
+    if *object_type == ObjectType::Tree {
+        let tree = git_fs::get_tree_object(hash)?;
+
+        for entry in tree.entries {
+            writeln!(
+                stdout,
+                "{:06} {} {}\t{}",
+                entry.mode,
+                entry.object_type(),
+                entry.hash,
+                entry.name
+            )?;
+        }
+    } else {
+        // blobs are written as raw bytes, commits and tags are already text
+        stdout.write_all(data)?;
+    }
+
+    stdout.flush()?;
+
+    Ok(())
+}
+
+// one line at a time, each answer flushed before the next line is read,
+// so a caller can pipe names in and read the answers as they come
+async fn cat_file_batch(with_content: bool) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        if std::io::stdin().read_line(&mut line)? == 0 {
+            break;
+        }
+
+        let name = line.trim();
+
+        if name.is_empty() {
+            continue;
+        }
+
+        let Some((hash, object)) = read_object(name).await? else {
+            writeln!(stdout, "{} missing", name)?;
+            stdout.flush()?;
+            continue;
+        };
+
+        writeln!(
+            stdout,
+            "{} {} {}",
+            hash, object.header.object_type, object.header.size
+        )?;
+
+        if with_content {
+            stdout.write_all(&object.data)?;
+            writeln!(stdout)?;
+        }
+
+        stdout.flush()?;
+    }
 
     Ok(())
 }
@@ -1,17 +1,197 @@
-use crate::commands::{init, pull};
+use crate::commands::{default_branch, get_fetchable_refs, sync_down, DEFAULT_BRANCH};
+use crate::contract_interaction::ContractInteraction;
+use crate::git::config::{Config, ConfigScope};
+use crate::git::filter::ObjectFilter;
 use crate::git::head::update_current_files_to_current_head;
+use crate::git::refs::Ref;
+use crate::git::refspec::Refspec;
+use crate::git::repository::Repository;
+use crate::remote::{validate_contract_address, Remote, DEFAULT_REMOTE};
+use anyhow::bail;
+use colored::Colorize;
+use std::path::Path;
+
+#[derive(Debug, Default, Clone)]
+pub struct CloneOptions {
+    /// Create a repository without a working tree, with the remote
+    /// branches as its own branches.
+    pub bare: bool,
+    /// The branch to check out instead of the remote's default branch.
+    pub branch: Option<String>,
+    /// Don't check out `HEAD` after the clone.
+    pub no_checkout: bool,
+    /// Only download the last `depth` commits of history, implies
+    /// `single_branch`.
+    pub depth: Option<usize>,
+    /// Only fetch the branch that is checked out, without tags.
+    pub single_branch: bool,
+    /// Leave blobs out of the download, to be fetched when needed.
+    pub filter: Option<ObjectFilter>,
+}
+
+// like git, clone only into a new or empty directory, or into the current
+// directory when none is given and it isn't a repository yet
+fn check_destination(dir: &Path, explicit: bool) -> anyhow::Result<()> {
+    if Repository::open(dir).is_ok() {
+        bail!("{} is already a git repository", dir.display());
+    }
+
+    if explicit && dir.exists() && std::fs::read_dir(dir)?.next().is_some() {
+        bail!(
+            "Destination path {} already exists and is not an empty directory",
+            dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Clone a repository contract into `dir` (the current directory when not
+/// given): fetch its branches and tags, create the branch the remote's
+/// default branch (or `--branch`) and check it out. A shallow or
+/// single-branch clone only downloads the history of that branch, a
+/// partial clone leaves blobs on the contract until they are read.
+pub async fn clone(
+    contract_address: String,
+    dir: Option<String>,
+    options: &CloneOptions,
+) -> anyhow::Result<()> {
+    validate_contract_address(&contract_address)?;
+
+    let dir = Path::new(dir.as_deref().unwrap_or(".")).to_path_buf();
+
+    check_destination(&dir, dir != Path::new("."))?;
 
-pub async fn clone(contract_address: String) -> anyhow::Result<()> {
     println!(
-        "Cloning repository contract with address: {}",
-        contract_address
+        "Cloning repository contract {} into {}",
+        contract_address,
+        dir.display()
     );
 
-    init(contract_address.clone()).await?;
+    let contract = ContractInteraction::new_with_address(&contract_address)?;
+    let remote_refs = get_fetchable_refs(&contract).await?;
+
+    let branch = match &options.branch {
+        Some(branch) => {
+            if !remote_refs.contains_key(&format!("refs/heads/{}", branch)) {
+                bail!("Remote branch {} not found in {}", branch, contract_address);
+            }
+
+            branch.clone()
+        }
+        None => default_branch(&remote_refs)
+            .and_then(|head| head.strip_prefix("refs/heads/").map(str::to_string))
+            .unwrap_or(DEFAULT_BRANCH.to_string()),
+    };
+
+    std::fs::create_dir_all(&dir)?;
+
+    if options.bare {
+        Repository::init_bare(&dir, &branch)?;
+    } else {
+        Repository::init(&dir, &branch)?;
+    }
+
+    // the rest of dgit works on the repository of the current directory
+    std::env::set_current_dir(&dir)?;
+
+    Remote::add(DEFAULT_REMOTE, &contract_address, None, None)?;
+
+    let single_branch = options.single_branch || options.depth.is_some();
+
+    // a bare clone mirrors the remote branches as its own
+    let fetch_refspec = match (single_branch, options.bare) {
+        (false, false) => None,
+        (false, true) => Some(Refspec::new(true, "refs/heads/*", "refs/heads/*")),
+        (true, false) => Some(Refspec::new(
+            true,
+            &format!("refs/heads/{}", branch),
+            &format!("refs/remotes/{}/{}", DEFAULT_REMOTE, branch),
+        )),
+        (true, true) => Some(Refspec::new(
+            true,
+            &format!("refs/heads/{}", branch),
+            &format!("refs/heads/{}", branch),
+        )),
+    };
+
+    if let Some(refspec) = fetch_refspec {
+        Config::set_value(
+            ConfigScope::Local,
+            &format!("remote.{}.fetch", DEFAULT_REMOTE),
+            &refspec.to_string(),
+        )?;
+    }
+
+    if let Some(filter) = options.filter {
+        // recorded like git does, so missing blobs are fetched from origin
+        Config::set_value(ConfigScope::Local, "core.repositoryformatversion", "1")?;
+        Config::set_value(
+            ConfigScope::Local,
+            "extensions.partialClone",
+            DEFAULT_REMOTE,
+        )?;
+        Config::set_value(
+            ConfigScope::Local,
+            &format!("remote.{}.promisor", DEFAULT_REMOTE),
+            "true",
+        )?;
+        Config::set_value(
+            ConfigScope::Local,
+            &format!("remote.{}.partialclonefilter", DEFAULT_REMOTE),
+            &filter.to_string(),
+        )?;
+    }
+
+    let remote = Remote::get(DEFAULT_REMOTE)?;
+
+    let mut refspecs = remote.fetch.clone();
+
+    if !single_branch {
+        refspecs.push(Refspec::new(false, "refs/tags/*", "refs/tags/*"));
+    }
+
+    sync_down(&remote, &refspecs, options.depth).await?;
+
+    if remote_refs.is_empty() {
+        println!("warning: You appear to have cloned an empty repository.");
+        return Ok(());
+    }
+
+    if options.bare {
+        println!(
+            "{}",
+            format!("Cloned bare repository on {}", branch).green()
+        );
+        return Ok(());
+    }
+
+    let Some(tracking_ref) = remote.tracking_ref(&format!("refs/heads/{}", branch)) else {
+        bail!("Remote branch {} is not fetched by {}", branch, remote.name);
+    };
+
+    let head = Ref::new(&tracking_ref).resolve().await?;
+
+    Ref::new(&format!("refs/heads/{}", branch))
+        .update(&head, &format!("clone: from {}", contract_address))
+        .await?;
+
+    Config::set_value(
+        ConfigScope::Local,
+        &format!("branch.{}.remote", branch),
+        &remote.name,
+    )?;
+    Config::set_value(
+        ConfigScope::Local,
+        &format!("branch.{}.merge", branch),
+        &format!("refs/heads/{}", branch),
+    )?;
 
-    pull().await?;
+    if !options.no_checkout {
+        update_current_files_to_current_head().await?;
+    }
 
-    update_current_files_to_current_head().await?;
+    println!("{}", format!("Cloned {} at {}", branch, head).green());
 
     Ok(())
 }
@@ -1,46 +1,93 @@
+use crate::contract_interaction::ContractInteraction;
 use crate::git::head::{resolve_head, update_head};
-use crate::git::objects::commit::{CommitAuthor, CommitContent, CommitObject};
+use crate::git::identity::{
+    config_value, parse_date, resolve_date, resolve_identity, Identity, IdentityRole,
+};
+use crate::git::objects::commit::{CommitContent, CommitObject};
 use crate::git::objects::tree::TreeObject;
+use crate::git::repository::Repository;
 use crate::git::traits::{Hash, ObjectSave};
-use crate::git::utils::detect_git_dir;
-use std::path::Path;
+use crate::signature::{sign, signing_key};
+use std::str::FromStr;
 
-pub async fn commit(message: Option<String>) -> anyhow::Result<String> {
+#[derive(Debug, Default, Clone)]
+pub struct CommitOptions {
+    /// Override the author, in the form `Name <email>`.
+    pub author: Option<String>,
+    /// Override the author date.
+    pub date: Option<String>,
+    /// Use the Ethereum account that will push as the identity.
+    pub eth_identity: bool,
+    /// Sign the commit with the Ethereum key it will be pushed with.
+    pub sign: bool,
+}
+
+// `<address>@dgit`, named after `user.name` when set
+async fn eth_identity() -> anyhow::Result<Identity> {
+    let address = ContractInteraction::signer_address().await?;
+
+    Ok(Identity {
+        name: config_value("user.name").unwrap_or_else(|| address.clone()),
+        email: format!("{}@dgit", address),
+    })
+}
+
+pub async fn commit(message: Option<String>, options: &CommitOptions) -> anyhow::Result<String> {
     let message = message.unwrap_or_else(|| "<blank>".to_string());
 
     let head = resolve_head().await?;
 
     let parent = if head.is_empty() { vec![] } else { vec![head] };
 
-    let tree_obj = TreeObject::write_tree_object(Path::new(&detect_git_dir()?).parent().unwrap())?;
+    let tree_obj = TreeObject::write_tree_object(Repository::current()?.work_tree()?)?;
     let tree_hash = tree_obj.hash();
 
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs() as i64;
+    let committer = if options.eth_identity {
+        eth_identity().await?
+    } else {
+        resolve_identity(IdentityRole::Committer)?
+    };
+
+    let author = match &options.author {
+        Some(author) => Identity::from_str(author)?,
+        None if options.eth_identity => committer.clone(),
+        None => resolve_identity(IdentityRole::Author)?,
+    };
+
+    let author_date = match &options.date {
+        Some(date) => parse_date(date)?,
+        None => resolve_date(IdentityRole::Author)?,
+    };
 
-    let commit_object = CommitObject::new(CommitContent {
+    let mut content = CommitContent {
         tree_sha: tree_hash,
         parent_sha: parent,
-        author: CommitAuthor {
-            name: "Varex".to_string(),
-            email: "example@exampe.com".to_string(),
-            timestamp,
-            timezone: "+0000".to_string(),
-        },
-        committer: CommitAuthor {
-            name: "Varex".to_string(),
-            email: "example@exampe.com".to_string(),
-            timestamp,
-            timezone: "+0000".to_string(),
-        },
+        author: author.signature(author_date),
+        committer: committer.signature(resolve_date(IdentityRole::Committer)?),
+        signature: None,
         message,
-    });
+    };
+
+    if options.sign {
+        content.signature = Some(sign(&content.payload(), &signing_key()?)?);
+    }
+
+    let reflog_message = format!(
+        "{}: {}",
+        if content.parent_sha.is_empty() {
+            "commit (initial)"
+        } else {
+            "commit"
+        },
+        content.message.lines().next().unwrap_or_default()
+    );
+
+    let commit_object = CommitObject::new(content);
 
     commit_object.save_object()?;
 
     let hash = commit_object.hash();
-    update_head(&hash).await?;
+    update_head(&hash, &reflog_message).await?;
 
     Ok(hash)
 }
@@ -0,0 +1,67 @@
+use crate::error::Error;
+use crate::git::config::{Config, ConfigKey, ConfigScope};
+use anyhow::{bail, Result};
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub enum ConfigAction {
+    /// Print the value of a key.
+    Get(String),
+    /// Replace all values of a key.
+    Set(String, String),
+    /// Add a value to a multi-valued key.
+    Add(String, String),
+    /// Remove all values of a key.
+    Unset(String),
+    /// List all variables with their values.
+    List,
+}
+
+pub fn config(scope: Option<ConfigScope>, action: ConfigAction) -> Result<()> {
+    match action {
+        ConfigAction::Get(key) => {
+            let key = ConfigKey::from_str(&key)?;
+
+            let value = match scope {
+                Some(scope) => Config::open_scope(scope)?.get(&key),
+                None => Config::load()?.get(&key.to_string()),
+            };
+
+            match value {
+                Some(value) => println!("{}", value),
+                // like git, a missing key is reported through the exit code only
+                None => return Err(Error::Exit(1).into()),
+            }
+        }
+        ConfigAction::Set(key, value) => {
+            Config::set_value(scope.unwrap_or(ConfigScope::Local), &key, &value)?;
+        }
+        ConfigAction::Add(key, value) => {
+            let mut file = Config::open_scope(scope.unwrap_or(ConfigScope::Local))?;
+
+            file.add(&ConfigKey::from_str(&key)?, &value);
+            file.save()?;
+        }
+        ConfigAction::Unset(key) => {
+            if !Config::unset_value(scope.unwrap_or(ConfigScope::Local), &key)? {
+                bail!("No such key: {}", key);
+            }
+        }
+        ConfigAction::List => {
+            let entries = match scope {
+                Some(scope) => Config::open_scope(scope)?.entries(),
+                None => Config::load()?
+                    .entries()
+                    .into_iter()
+                    .map(|(_, key, value)| (key, value))
+                    .collect(),
+            };
+
+            for (key, value) in entries {
+                println!("{}={}", key, value);
+            }
+        }
+    }
+
+    Ok(())
+}
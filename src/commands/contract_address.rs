@@ -1,8 +1,8 @@
-use crate::config::Config;
+use crate::remote::Remote;
 use colored::Colorize;
 
 pub async fn contract_address() -> anyhow::Result<()> {
-    let contract_address = Config::get_contract_address();
+    let contract_address = Remote::get(&Remote::default_name().await?)?.contract_address;
 
     println!(
         "Contract address: {}",
@@ -1,7 +1,11 @@
 use crate::commands::init;
-use crate::contract_interaction::ContractInteraction;
+use crate::contract_interaction::{address_to_string, ContractInteraction};
+use crate::git::repository::Repository;
+use crate::remote::{Remote, DEFAULT_REMOTE};
 use colored::Colorize;
 
+/// Deploy a new repository contract and bind it as `origin` of the current
+/// repository, creating the repository if needed.
 pub async fn deploy_repo_contract() -> anyhow::Result<String> {
     println!(
         "{}",
@@ -15,8 +19,19 @@ pub async fn deploy_repo_contract() -> anyhow::Result<String> {
         "{}",
         format!("Deployed repository contract with address: {}", address).green()
     );
+    println!(
+        "Owner: {}",
+        address_to_string(&contract.sender_address().await?)
+    );
 
-    init(address.clone()).await?;
+    if Repository::current().is_ok() && Remote::get(DEFAULT_REMOTE).is_ok() {
+        println!(
+            "Remote {} already exists, bind the new contract with `dgit remote add <name> {}`",
+            DEFAULT_REMOTE, address
+        );
+    } else {
+        init(Some(address.clone()), None).await?;
+    }
 
     Ok(address)
 }
@@ -0,0 +1,70 @@
+use crate::commands::{
+    default_branch, download_objects, get_fetchable_refs, ref_value, DEFAULT_BRANCH,
+};
+use crate::contract_interaction::ContractInteraction;
+use crate::git::repository::Repository;
+use crate::remote::Remote;
+use anyhow::{bail, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::Path;
+
+/// Write the refs and objects of a repository contract to a new bare git
+/// repository, without a working tree. Exports the contract of the default
+/// remote unless `contract_address` is given.
+pub async fn export(dir: &str, bare: bool, contract_address: Option<String>) -> Result<()> {
+    if !bare {
+        bail!("Only bare exports are supported, use `dgit clone` for a working tree");
+    }
+
+    let dir = Path::new(dir);
+
+    if dir.exists() && fs::read_dir(dir)?.next().is_some() {
+        bail!(
+            "Destination {} already exists and is not empty",
+            dir.display()
+        );
+    }
+
+    let contract = match contract_address {
+        Some(address) => ContractInteraction::new_with_address(&address)?,
+        None => ContractInteraction::for_remote(&Remote::get(&Remote::default_name().await?)?)?,
+    };
+
+    let refs = get_fetchable_refs(&contract).await?;
+
+    let head = default_branch(&refs).unwrap_or(format!("refs/heads/{}", DEFAULT_BRANCH));
+
+    let repository = Repository::init_bare(
+        dir,
+        head.strip_prefix("refs/heads/").unwrap_or(DEFAULT_BRANCH),
+    )?;
+
+    let tips = refs
+        .values()
+        .map(|data| ref_value(data))
+        .collect::<Vec<String>>();
+
+    let count = download_objects(&contract, &repository, &tips, |hash| {
+        println!("{}", format!("Downloaded object: {}", hash).cyan());
+    })
+    .await?;
+
+    for (name, data) in &refs {
+        repository.update_ref(name, &ref_value(data))?;
+    }
+
+    println!(
+        "{}",
+        format!(
+            "Exported {} objects and {} refs of {} to {}",
+            count,
+            refs.len(),
+            contract.address(),
+            dir.display()
+        )
+        .green()
+    );
+
+    Ok(())
+}
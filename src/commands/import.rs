@@ -0,0 +1,242 @@
+use crate::commands::{get_remote_refs, ref_value, upload_objects, DEFAULT_BRANCH};
+use crate::config::Config;
+use crate::contract_interaction::{ContractInteraction, Role};
+use crate::git::refs::Ref;
+use crate::git::repository::{hash_object_data, Repository};
+use crate::git::walk::parse_children;
+use anyhow::{bail, Result};
+use colored::Colorize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+// the refs saved per `add_refs` transaction, so importing a repository
+// with many tags stays below the block gas limit
+const REFS_PER_TRANSACTION: usize = 64;
+
+// branches and tags of the source repository
+fn read_source_refs(source: &Repository) -> Result<BTreeMap<String, String>> {
+    let mut refs = BTreeMap::new();
+
+    for (name, content) in source.refs()? {
+        let content = Ref::parse_ref_content(&String::from_utf8_lossy(&content));
+
+        // symbolic refs such as refs/remotes/origin/HEAD
+        if (name.starts_with("refs/heads/") || name.starts_with("refs/tags/"))
+            && !content.starts_with("refs/")
+        {
+            refs.insert(name, content);
+        }
+    }
+
+    Ok(refs)
+}
+
+// copy every object reachable from the refs into `staging` as loose
+// objects, so that packed objects can be uploaded one by one
+fn stage_objects(
+    source: &Repository,
+    refs: &BTreeMap<String, String>,
+    staging: &Repository,
+) -> Result<Vec<String>> {
+    let mut hashes = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue = refs.values().cloned().collect::<Vec<String>>();
+
+    while let Some(hash) = queue.pop() {
+        if !seen.insert(hash.clone()) {
+            continue;
+        }
+
+        let data = source.read_object(&hash)?;
+
+        staging.write_object(&data)?;
+
+        queue.extend(parse_children(data)?);
+
+        hashes.push(hash);
+    }
+
+    Ok(hashes)
+}
+
+// download every imported object back through the contract and check that
+// it hashes to the same name, and that the refs point where they should
+async fn verify_import(
+    contract: &ContractInteraction,
+    refs: &BTreeMap<String, String>,
+    hashes: &[String],
+    verify: &Repository,
+) -> Result<()> {
+    let remote_refs = get_remote_refs(contract).await?;
+
+    for (name, hash) in refs {
+        if remote_refs.get(name).map(|data| ref_value(data)).as_ref() != Some(hash) {
+            bail!("Verification failed: {} does not point to {}", name, hash);
+        }
+    }
+
+    let stored = contract.get_objects_by_hash(hashes.to_vec()).await?;
+
+    for (hash, object) in hashes.iter().zip(stored) {
+        let ipfs_url = object.ipfs_url;
+
+        if ipfs_url.is_empty() {
+            bail!(
+                "Verification failed: object {} is not on the contract",
+                hash
+            );
+        };
+
+        let Some(path) = verify.loose_object_path(hash) else {
+            bail!("Verification failed: {} is not an object name", hash);
+        };
+
+        crate::ipfs::download_from_ipfs(
+            &String::from_utf8_lossy(&ipfs_url),
+            &path.to_string_lossy(),
+        )
+        .await?;
+
+        if hash_object_data(&verify.read_object(hash)?) != *hash {
+            bail!("Verification failed: object {} is corrupted", hash);
+        }
+    }
+
+    Ok(())
+}
+
+async fn import_into(
+    source: &Repository,
+    contract_address: Option<String>,
+    verify: bool,
+    staging_dir: &Path,
+) -> Result<String> {
+    let refs = read_source_refs(source)?;
+
+    if refs.is_empty() {
+        bail!(
+            "Nothing to import: {} has no branches or tags",
+            source.git_dir().display()
+        );
+    }
+
+    let staging = Repository::init_bare(&staging_dir.join("staging"), DEFAULT_BRANCH)?;
+    let hashes = stage_objects(source, &refs, &staging)?;
+
+    println!(
+        "{}",
+        format!(
+            "Found {} refs and {} reachable objects",
+            refs.len(),
+            hashes.len()
+        )
+        .yellow()
+    );
+
+    let contract = match contract_address {
+        Some(address) => {
+            let contract = ContractInteraction::new_with_address(&address)?;
+
+            contract.require_role(Role::Writer).await?;
+
+            // don't silently rewrite the history of a repository in use
+            for (name, data) in get_remote_refs(&contract).await? {
+                if let Some(hash) = refs.get(&name) {
+                    if ref_value(&data) != *hash {
+                        bail!(
+                            "Contract already has {} at {}, refusing to overwrite it",
+                            name,
+                            ref_value(&data)
+                        );
+                    }
+                }
+            }
+
+            contract
+        }
+        None => {
+            println!("{}", "Deploying repository contract...".bright_blue());
+
+            ContractInteraction::deploy().await?
+        }
+    };
+
+    let address = contract.address();
+
+    let uploaded = upload_objects(
+        &contract,
+        Config::storage_backend()?,
+        &staging,
+        &hashes,
+        |hash| println!("{}", format!("Uploaded object: {}", hash).cyan()),
+    )
+    .await?
+    .count;
+
+    let remote_refs = get_remote_refs(&contract).await?;
+
+    let changed = refs
+        .iter()
+        .filter(|(name, hash)| {
+            remote_refs.get(*name).map(|data| ref_value(data)).as_ref() != Some(hash)
+        })
+        .collect::<Vec<_>>();
+
+    for batch in changed.chunks(REFS_PER_TRANSACTION) {
+        let names = batch
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<String>>();
+        let old = names
+            .iter()
+            .map(|name| remote_refs.get(name).cloned().unwrap_or_default())
+            .collect();
+        let data = batch
+            .iter()
+            .map(|(_, hash)| hash.as_bytes().to_vec())
+            .collect();
+
+        contract.add_refs(names.clone(), old, data).await?;
+
+        for name in names {
+            println!("{}", format!("Uploaded and saved ref: {}", name).cyan());
+        }
+    }
+
+    if verify {
+        println!("{}", "Verifying the imported repository...".yellow());
+
+        let verify_repository = Repository::init_bare(&staging_dir.join("verify"), DEFAULT_BRANCH)?;
+
+        verify_import(&contract, &refs, &hashes, &verify_repository).await?;
+    }
+
+    println!(
+        "{}",
+        format!(
+            "Imported {} objects ({} uploaded) and {} refs into {}",
+            hashes.len(),
+            uploaded,
+            refs.len(),
+            address
+        )
+        .green()
+    );
+
+    Ok(address)
+}
+
+/// Upload the history of an existing git repository (branches, tags and
+/// every object reachable from them) to a repository contract, deploying
+/// a new one unless `contract_address` is given.
+pub async fn import(path: &str, contract_address: Option<String>, verify: bool) -> Result<String> {
+    let source = Repository::open(Path::new(path))?;
+
+    let staging_dir = std::env::temp_dir().join(format!("dgit-import-{}", std::process::id()));
+
+    let result = import_into(&source, contract_address, verify, &staging_dir).await;
+
+    let _ = std::fs::remove_dir_all(&staging_dir);
+
+    result
+}
@@ -1,33 +1,66 @@
-use crate::commands::commit;
 use crate::git::config::Config;
-use anyhow::Result;
+use crate::git::repository::Repository;
+use crate::remote::{validate_contract_address, Remote, DEFAULT_REMOTE};
+use anyhow::{bail, Result};
+use colored::Colorize;
 use std::fs;
+use std::path::Path;
 
-pub async fn init(contract_address: String) -> Result<()> {
-    let _ = fs::create_dir(".git");
-    let _ = fs::create_dir(".git/objects");
-    let _ = fs::create_dir(".git/refs");
-    let _ = fs::write(".git/HEAD", "ref: refs/heads/main\n");
-    let _ = fs::create_dir(".git/refs/heads");
-    let _ = fs::create_dir(".git/refs/tags");
-    let _ = fs::create_dir(".git/refs/remotes");
+pub const DEFAULT_BRANCH: &str = "main";
 
-    let commit = commit(None).await?;
+/// Create an empty repository with no commits, or reinitialize an existing
+/// one. A contract given here becomes the `origin` remote, otherwise one
+/// can be bound later with `remote add` or `deploy`.
+pub async fn init(contract_address: Option<String>, initial_branch: Option<String>) -> Result<()> {
+    if let Some(contract_address) = &contract_address {
+        validate_contract_address(contract_address)?;
+    }
 
-    let _ = fs::write(".git/refs/heads/main", commit);
+    let branch = initial_branch
+        .clone()
+        .or_else(|| Config::load().ok()?.get("init.defaultBranch"))
+        .unwrap_or(DEFAULT_BRANCH.to_string());
 
-    // verify correct contract address using regex
-    let re = regex::Regex::new(r"0x[a-fA-F0-9]{40}")?;
+    let (repository, reinitialized) = Repository::init(Path::new("."), &branch)?;
 
-    if !re.is_match(&contract_address) || contract_address.len() != 42 {
-        anyhow::bail!("Invalid contract address");
+    if let (true, Some(initial_branch)) = (reinitialized, &initial_branch) {
+        eprintln!(
+            "{}",
+            format!(
+                "warning: re-init: ignored --initial-branch={}",
+                initial_branch
+            )
+            .yellow()
+        );
     }
 
-    let config = Config::new(contract_address);
+    if let Some(contract_address) = contract_address {
+        match Remote::get(DEFAULT_REMOTE) {
+            Ok(remote)
+                if remote
+                    .contract_address
+                    .eq_ignore_ascii_case(&contract_address) => {}
+            Ok(remote) => bail!(
+                "Remote {} already points at {}, use `dgit remote add` for another contract",
+                DEFAULT_REMOTE,
+                remote.contract_address
+            ),
+            Err(_) => {
+                Remote::add(DEFAULT_REMOTE, &contract_address, None, None)?;
+            }
+        }
+    }
 
-    config.save().await?;
+    let git_dir = fs::canonicalize(repository.git_dir())?;
 
-    println!("Initialized git directory");
+    if reinitialized {
+        println!(
+            "Reinitialized existing git repository in {}",
+            git_dir.display()
+        );
+    } else {
+        println!("Initialized empty git repository in {}", git_dir.display());
+    }
 
     Ok(())
 }
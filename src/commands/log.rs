@@ -0,0 +1,119 @@
+use crate::commands::{check_signature, signature_contract};
+use crate::git::git_fs::get_commit_object;
+use crate::git::objects::commit::CommitAuthor;
+use crate::git::repository::Repository;
+use crate::git::revision::resolve_revision;
+use anyhow::Result;
+use chrono::{FixedOffset, TimeZone};
+use colored::Colorize;
+use std::collections::{BTreeSet, BinaryHeap};
+
+#[derive(Debug, Default, Clone)]
+pub struct LogOptions {
+    /// Stop after this many commits.
+    pub max_count: Option<usize>,
+    /// Check the signature of each commit against its pusher.
+    pub show_signature: bool,
+}
+
+// `Mon Jun 1 06:04:32 2021 +0200`, in the timezone of the signature
+fn format_date(signature: &CommitAuthor) -> String {
+    let tz = &signature.timezone;
+
+    let offset = tz
+        .get(1..)
+        .unwrap_or_default()
+        .parse::<i32>()
+        .ok()
+        .map(|hhmm| {
+            (hhmm / 100 * 3600 + hhmm % 100 * 60) * if tz.starts_with('-') { -1 } else { 1 }
+        })
+        .and_then(FixedOffset::east_opt)
+        .unwrap_or(FixedOffset::east_opt(0).unwrap());
+
+    match offset.timestamp_opt(signature.timestamp, 0).single() {
+        Some(date) => format!("{} {}", date.format("%a %b %-d %H:%M:%S %Y"), tz),
+        None => format!("{} {}", signature.timestamp, tz),
+    }
+}
+
+/// Show the history of `revision` (`HEAD` by default), newest commits
+/// first.
+pub async fn log(revision: Option<String>, options: &LogOptions) -> Result<()> {
+    let repository = Repository::current()?;
+    let shallow = repository.shallow_commits()?;
+
+    let contract = if options.show_signature {
+        signature_contract().await?
+    } else {
+        None
+    };
+
+    let head = resolve_revision(revision.as_deref().unwrap_or("HEAD")).await?;
+
+    // by committer date, like git does without other ordering options
+    let mut queue = BinaryHeap::new();
+    let mut seen = BTreeSet::new();
+
+    queue.push((
+        get_commit_object(&head)?.content.committer.timestamp,
+        head.clone(),
+    ));
+    seen.insert(head);
+
+    let mut shown = 0;
+
+    while let Some((_, hash)) = queue.pop() {
+        if options.max_count.is_some_and(|max| shown >= max) {
+            break;
+        }
+
+        let commit = get_commit_object(&hash)?;
+
+        shown += 1;
+
+        println!("{}", format!("commit {}", hash).yellow());
+
+        if options.show_signature {
+            check_signature(&hash, &commit, contract.as_ref())
+                .await?
+                .print();
+        }
+
+        let content = &commit.content;
+
+        if content.parent_sha.len() > 1 {
+            let parents = content
+                .parent_sha
+                .iter()
+                .map(|parent| parent.get(..7).unwrap_or(parent))
+                .collect::<Vec<&str>>();
+
+            println!("Merge: {}", parents.join(" "));
+        }
+
+        println!("Author: {} <{}>", content.author.name, content.author.email);
+        println!("Date:   {}", format_date(&content.author));
+        println!();
+
+        for line in content.message.lines() {
+            println!("    {}", line);
+        }
+
+        println!();
+
+        // the parents of shallow commits were never downloaded
+        if shallow.contains(&hash) {
+            continue;
+        }
+
+        for parent in &content.parent_sha {
+            if seen.insert(parent.clone()) {
+                let timestamp = get_commit_object(parent)?.content.committer.timestamp;
+                queue.push((timestamp, parent.clone()));
+            }
+        }
+    }
+
+    Ok(())
+}
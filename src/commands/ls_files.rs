@@ -0,0 +1,97 @@
+use crate::git::git_fs::get_tree_object;
+use crate::git::head::resolve_head;
+use crate::git::ignore::IgnoreRules;
+use crate::git::objects::tree::TreeEntry;
+use crate::git::repository::Repository;
+use crate::git::revision::peel_to_tree;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::Path;
+use walkdir::WalkDir;
+
+#[derive(Debug, Default, Clone)]
+pub struct LsFilesOptions {
+    /// Show tracked files (the default when no other mode is given).
+    pub cached: bool,
+    /// Show untracked files in the working tree.
+    pub others: bool,
+    /// Show only files matching the ignore rules.
+    pub ignored: bool,
+    /// Show mode, object name and stage number of tracked files.
+    pub stage: bool,
+}
+
+// files recorded in the tree of the current HEAD
+async fn tracked_files() -> Result<Vec<(String, TreeEntry)>> {
+    let head = resolve_head().await?;
+
+    if head.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let tree = get_tree_object(&peel_to_tree(&head)?)?;
+
+    tree.get_entries_recursive("")
+}
+
+fn working_tree_files(work_tree: &Path) -> Vec<String> {
+    WalkDir::new(work_tree)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            entry
+                .path()
+                .strip_prefix(work_tree)
+                .ok()
+                .and_then(|path| path.to_str())
+                .map(|path| path.replace('\\', "/"))
+        })
+        .collect()
+}
+
+pub async fn ls_files(options: &LsFilesOptions) -> Result<()> {
+    let repository = Repository::current()?;
+    let work_tree = repository.work_tree()?;
+    let rules = IgnoreRules::load(&repository)?;
+
+    let show_cached = options.cached || options.stage || !options.others;
+
+    let tracked = tracked_files().await?;
+
+    if show_cached {
+        for (path, entry) in &tracked {
+            if options.ignored && !rules.is_ignored(path, false) {
+                continue;
+            }
+
+            if options.stage {
+                println!("{:06} {} 0\t{}", entry.mode, entry.hash, path);
+            } else {
+                println!("{}", path);
+            }
+        }
+    }
+
+    if options.others {
+        let tracked = tracked
+            .iter()
+            .map(|(path, _)| path.as_str())
+            .collect::<HashSet<&str>>();
+
+        let mut others = working_tree_files(work_tree)
+            .into_iter()
+            .filter(|path| !tracked.contains(path.as_str()))
+            .filter(|path| rules.is_ignored(path, false) == options.ignored)
+            .collect::<Vec<String>>();
+
+        others.sort();
+
+        for path in others {
+            println!("{}", path);
+        }
+    }
+
+    Ok(())
+}
@@ -1,15 +1,103 @@
-use crate::git::git_fs::get_tree_object;
+use crate::git::git_fs::{get_object_header, get_tree_object};
+use crate::git::objects::header::ObjectType;
+use crate::git::objects::tree::TreeEntry;
+use crate::git::revision::{peel_to_tree, resolve_revision};
 use anyhow::Result;
 
-pub fn ls_tree(path: &str, is_names_only: bool) -> Result<()> {
-    let tree_object = get_tree_object(path)?;
+#[derive(Debug, Default, Clone)]
+pub struct LsTreeOptions {
+    /// Recurse into sub-trees.
+    pub recursive: bool,
+    /// Show tree entries even when going to recurse them.
+    pub show_trees: bool,
+    /// Show only the named tree entries themselves, not their children.
+    pub only_trees: bool,
+    /// Show the object size of blob entries.
+    pub long: bool,
+    /// List only the paths.
+    pub name_only: bool,
+}
+
+pub async fn ls_tree(tree_ish: &str, paths: &[String], options: &LsTreeOptions) -> Result<()> {
+    let hash = resolve_revision(tree_ish).await?;
+    let tree_hash = peel_to_tree(&hash)?;
 
-    for entry in tree_object.entries {
-        if is_names_only {
-            println!("{}", entry.name);
+    let filters = paths
+        .iter()
+        .map(|path| path.trim_end_matches('/').to_string())
+        .collect::<Vec<String>>();
+
+    list_tree(&tree_hash, "", &filters, options)
+}
+
+fn list_tree(hash: &str, prefix: &str, filters: &[String], options: &LsTreeOptions) -> Result<()> {
+    let tree = get_tree_object(hash)?;
+
+    for entry in tree.entries {
+        let path = if prefix.is_empty() {
+            entry.name.clone()
         } else {
-            println!("{:06}\t{:20}\t{:30}", entry.mode, entry.name, entry.hash);
+            format!("{}/{}", prefix, entry.name)
+        };
+
+        let is_tree = entry.object_type() == ObjectType::Tree;
+
+        let matched = filters.is_empty()
+            || filters
+                .iter()
+                .any(|filter| path == *filter || path.starts_with(&format!("{}/", filter)));
+
+        // a tree that has to be entered to reach one of the filtered paths
+        let leads_to_filter = is_tree
+            && filters
+                .iter()
+                .any(|filter| filter.starts_with(&format!("{}/", path)));
+
+        if !matched && !leads_to_filter {
+            continue;
         }
+
+        let recurse = is_tree && (options.recursive || !matched);
+
+        let show = if is_tree {
+            !recurse || options.show_trees || options.only_trees
+        } else {
+            !options.only_trees
+        };
+
+        if show {
+            print_entry(&entry, &path, options)?;
+        }
+
+        if recurse {
+            list_tree(&entry.hash, &path, filters, options)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn print_entry(entry: &TreeEntry, path: &str, options: &LsTreeOptions) -> Result<()> {
+    if options.name_only {
+        println!("{}", path);
+        return Ok(());
+    }
+
+    let object_type = entry.object_type();
+
+    if options.long {
+        let size = if object_type == ObjectType::Blob {
+            get_object_header(&entry.hash)?.size.to_string()
+        } else {
+            "-".to_string()
+        };
+
+        println!(
+            "{:06} {} {} {:>7}\t{}",
+            entry.mode, object_type, entry.hash, size, path
+        );
+    } else {
+        println!("{:06} {} {}\t{}", entry.mode, object_type, entry.hash, path);
     }
 
     Ok(())
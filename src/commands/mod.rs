@@ -1,23 +1,47 @@
+mod access;
+mod account;
+mod bundle;
 mod cat_file;
 mod clone;
 mod commit;
+mod config;
 mod contract_address;
 mod deploy_repo_contract;
+mod export;
 mod hash_object;
+mod import;
 mod init;
+mod log;
+mod ls_files;
 mod ls_tree;
+mod protect;
+mod reflog;
+mod remote;
 mod status;
 mod sync;
+mod verify_commit;
 pub mod write_tree;
 
+pub use access::*;
+pub use account::*;
+pub use bundle::*;
 pub use cat_file::*;
 pub use clone::*;
 pub use commit::*;
+pub use config::*;
 pub use contract_address::*;
 pub use deploy_repo_contract::*;
+pub use export::*;
 pub use hash_object::*;
+pub use import::*;
 pub use init::*;
+pub use log::*;
+pub use ls_files::*;
 pub use ls_tree::*;
+pub use protect::*;
+pub use reflog::*;
+pub use remote::*;
 pub use status::*;
 pub use sync::*;
+pub use verify_commit::*;
 pub use write_tree::*;
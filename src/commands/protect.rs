@@ -0,0 +1,113 @@
+use crate::commands::{print_costs, resolve_remote};
+use crate::contract_interaction::{
+    address_to_string, parse_address, ContractInteraction, Protection, Role,
+};
+use crate::git::refspec::expand_ref_name;
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+#[derive(Debug, Clone)]
+pub enum ProtectAction {
+    /// List the protected refs and their rules.
+    List,
+    /// Protect a ref, replacing its rules if it already was.
+    Add {
+        name: String,
+        /// The only addresses that may update the ref, any writer when empty.
+        pushers: Vec<String>,
+        allow_force: bool,
+        allow_deletion: bool,
+    },
+    /// Lift the protection of a ref.
+    Remove { name: String },
+}
+
+fn print_protection(name: &str, protection: &Protection) {
+    let pushers = if protection.pushers.is_empty() {
+        "any writer".to_string()
+    } else {
+        protection
+            .pushers
+            .iter()
+            .map(address_to_string)
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    println!("{}", name.green());
+    println!("  pushers: {}", pushers);
+    // the contract only refuses updates sent as force-pushes, it can't
+    // check that the others are fast-forwards
+    println!(
+        "  force-pushes: {}",
+        if protection.allow_force {
+            "allowed"
+        } else {
+            "refused, fast-forwards are not checked on-chain"
+        }
+    );
+    println!(
+        "  deletion: {}",
+        if protection.allow_deletion {
+            "allowed"
+        } else {
+            "forbidden"
+        }
+    );
+}
+
+/// Manage the protected refs of the repository contract of `remote`, or of
+/// the default remote.
+pub async fn protect(remote: Option<String>, action: ProtectAction) -> Result<()> {
+    let remote = resolve_remote(remote).await?;
+    let contract = ContractInteraction::for_remote(&remote)?;
+
+    match action {
+        ProtectAction::List => {
+            for name in contract.get_protected_refs().await? {
+                if let Some(protection) = contract.get_protection(name.clone()).await? {
+                    print_protection(&name, &protection);
+                }
+            }
+        }
+        ProtectAction::Add {
+            name,
+            pushers,
+            allow_force,
+            allow_deletion,
+        } => {
+            let name = expand_ref_name(&name);
+            let protection = Protection {
+                pushers: pushers
+                    .iter()
+                    .map(|pusher| parse_address(pusher))
+                    .collect::<crate::error::Result<_>>()?,
+                allow_force,
+                allow_deletion,
+            };
+
+            contract.require_role(Role::Maintainer).await?;
+
+            let cost = contract.protect_ref(name.clone(), &protection).await?;
+
+            print_protection(&name, &protection);
+            print_costs(&[cost]);
+        }
+        ProtectAction::Remove { name } => {
+            let name = expand_ref_name(&name);
+
+            if contract.get_protection(name.clone()).await?.is_none() {
+                bail!("{} is not protected", name);
+            }
+
+            contract.require_role(Role::Maintainer).await?;
+
+            let cost = contract.unprotect_ref(name.clone()).await?;
+
+            println!("{}", format!("{} is no longer protected", name).green());
+            print_costs(&[cost]);
+        }
+    }
+
+    Ok(())
+}
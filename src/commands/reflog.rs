@@ -0,0 +1,179 @@
+use crate::commands::{ref_value, resolve_remote};
+use crate::contract_interaction::{
+    address_to_string, parse_address, ContractInteraction, RefLogEntry,
+};
+use crate::git::reflog::read_reflog;
+use crate::git::refspec::expand_ref_name;
+use crate::git::repository::Repository;
+use crate::git::revision::reflog_ref;
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// how many history entries are read from the contract per call
+const REF_LOG_PAGE: u64 = 256;
+
+#[derive(Debug, Default, Clone)]
+pub struct ReflogOptions {
+    /// Stop after this many entries.
+    pub max_count: Option<usize>,
+    /// Only show the updates made by this address, on a remote.
+    pub pusher: Option<String>,
+    /// Print the entries as JSON instead.
+    pub json: bool,
+}
+
+/// An entry of the local reflog as printed with `--json`.
+#[derive(Debug, Serialize)]
+struct LocalReflogEntry {
+    selector: String,
+    old: String,
+    new: String,
+    committer: String,
+    timestamp: i64,
+    timezone: String,
+    message: String,
+}
+
+/// An entry of the remote reflog as printed with `--json`.
+#[derive(Debug, Serialize)]
+struct RemoteReflogEntry {
+    id: u64,
+    #[serde(rename = "ref")]
+    name: String,
+    /// The object the ref was set to, `None` when it was deleted.
+    value: Option<String>,
+    pusher: String,
+    block_number: u64,
+    timestamp: u64,
+}
+
+impl From<&RefLogEntry> for RemoteReflogEntry {
+    fn from(entry: &RefLogEntry) -> Self {
+        RemoteReflogEntry {
+            id: entry.id,
+            name: entry.reference.name.clone(),
+            value: entry
+                .reference
+                .is_active
+                .then(|| ref_value(&entry.reference.data)),
+            pusher: address_to_string(&entry.reference.pusher),
+            block_number: entry.block_number,
+            timestamp: entry.timestamp,
+        }
+    }
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    match Utc.timestamp_opt(timestamp as i64, 0).single() {
+        Some(date) => date.format("%a %b %-d %H:%M:%S %Y +0000").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// Show the changes of `name` (`HEAD` by default) recorded in the local
+/// reflog, newest first, as `<name>@{n}` selectors.
+pub async fn reflog(name: Option<String>, options: &ReflogOptions) -> Result<()> {
+    let name = name.unwrap_or_else(|| "HEAD".to_string());
+    let ref_name = reflog_ref(&name)?;
+
+    let entries = read_reflog(&Repository::current()?, &ref_name)?
+        .into_iter()
+        .rev()
+        .take(options.max_count.unwrap_or(usize::MAX))
+        .enumerate()
+        .map(|(n, entry)| LocalReflogEntry {
+            selector: format!("{}@{{{}}}", name, n),
+            old: entry.old,
+            new: entry.new,
+            committer: format!("{} <{}>", entry.committer.name, entry.committer.email),
+            timestamp: entry.committer.timestamp,
+            timezone: entry.committer.timezone,
+            message: entry.message,
+        })
+        .collect::<Vec<LocalReflogEntry>>();
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    for entry in entries {
+        println!(
+            "{} {}: {}",
+            entry.new[..7.min(entry.new.len())].yellow(),
+            entry.selector,
+            entry.message
+        );
+    }
+
+    Ok(())
+}
+
+/// Show every update of the refs of the contract of `remote`, or of the
+/// default remote, with who pushed it and when: of `name` only if given,
+/// newest first per ref.
+pub async fn remote_reflog(
+    remote: Option<String>,
+    name: Option<String>,
+    options: &ReflogOptions,
+) -> Result<()> {
+    let remote = resolve_remote(remote).await?;
+    let contract = ContractInteraction::for_remote(&remote)?;
+
+    let name = name.map(|name| expand_ref_name(&name));
+    let pusher = options.pusher.as_deref().map(parse_address).transpose()?;
+
+    let length = contract.get_refs_length().await?.as_u64();
+    let mut entries = Vec::new();
+
+    for start in (0..length).step_by(REF_LOG_PAGE as usize) {
+        for entry in contract.get_ref_log(start, REF_LOG_PAGE).await? {
+            let matches = name
+                .as_ref()
+                .is_none_or(|name| *name == entry.reference.name)
+                && pusher.is_none_or(|pusher| pusher == entry.reference.pusher);
+
+            if matches {
+                entries.push(RemoteReflogEntry::from(&entry));
+            }
+        }
+    }
+
+    entries.reverse();
+    entries.truncate(options.max_count.unwrap_or(usize::MAX));
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    let mut by_ref = BTreeMap::<&str, Vec<&RemoteReflogEntry>>::new();
+
+    for entry in &entries {
+        by_ref.entry(&entry.name).or_default().push(entry);
+    }
+
+    for (name, entries) in by_ref {
+        println!("{}", name.green());
+
+        for entry in entries {
+            let value = match &entry.value {
+                Some(value) => value.yellow(),
+                None => "deleted".red(),
+            };
+
+            println!(
+                "  {} by {} in block {} at {}",
+                value,
+                entry.pusher,
+                entry.block_number,
+                format_timestamp(entry.timestamp)
+            );
+        }
+    }
+
+    Ok(())
+}
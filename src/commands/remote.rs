@@ -0,0 +1,84 @@
+use crate::config::StorageBackend;
+use crate::remote::Remote;
+use anyhow::Result;
+use colored::Colorize;
+use std::str::FromStr;
+
+#[derive(Debug, Clone)]
+pub enum RemoteAction {
+    List {
+        verbose: bool,
+    },
+    Add {
+        name: String,
+        contract_address: String,
+        rpc_url: Option<String>,
+        storage: Option<String>,
+    },
+    Remove {
+        name: String,
+    },
+    Rename {
+        old: String,
+        new: String,
+    },
+}
+
+pub fn remote(action: RemoteAction) -> Result<()> {
+    match action {
+        RemoteAction::List { verbose } => {
+            for remote in Remote::list()? {
+                if !verbose {
+                    println!("{}", remote.name);
+                    continue;
+                }
+
+                let mut details = Vec::new();
+
+                if let Some(rpc_url) = &remote.rpc_url {
+                    details.push(format!("rpc: {}", rpc_url));
+                }
+
+                if let Some(storage) = &remote.storage {
+                    details.push(format!("storage: {}", storage));
+                }
+
+                println!(
+                    "{}\t{} {}",
+                    remote.name,
+                    remote.url().bright_blue(),
+                    details.join(", ")
+                );
+            }
+        }
+        RemoteAction::Add {
+            name,
+            contract_address,
+            rpc_url,
+            storage,
+        } => {
+            let storage = storage
+                .map(|storage| StorageBackend::from_str(&storage))
+                .transpose()?;
+
+            let remote = Remote::add(&name, &contract_address, rpc_url.as_deref(), storage)?;
+
+            println!(
+                "{}",
+                format!("Added remote {} ({})", remote.name, remote.url()).green()
+            );
+        }
+        RemoteAction::Remove { name } => {
+            Remote::remove(&name)?;
+
+            println!("{}", format!("Removed remote {}", name).green());
+        }
+        RemoteAction::Rename { old, new } => {
+            Remote::rename(&old, &new)?;
+
+            println!("{}", format!("Renamed remote {} to {}", old, new).green());
+        }
+    }
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
-use crate::utils::get_object_hashes;
+use crate::git::repository::Repository;
+use crate::remote::Remote;
 use anyhow::Result;
 use colored::Colorize;
 use ethcontract::U256;
@@ -6,10 +7,11 @@ use ethcontract::U256;
 pub async fn status() -> Result<()> {
     println!("Checking repository status...");
 
-    let contract_address = crate::config::Config::get_contract_address();
+    let repository = Repository::current()?;
 
-    let contract =
-        crate::contract_interaction::ContractInteraction::new_with_address(&contract_address);
+    let remote = Remote::get(&Remote::default_name().await?)?;
+
+    let contract = crate::contract_interaction::ContractInteraction::for_remote(&remote)?;
 
     let mut count_down = 0usize;
 
@@ -17,17 +19,15 @@ pub async fn status() -> Result<()> {
 
     for index in 0..len_of_objects {
         let object = contract.get_object_by_id(U256::from(index)).await?;
-        let (prefix, hash) = object.hash.split_at(2);
-        let file_path = format!("./.git/objects/{}/{}", prefix, hash);
 
-        if std::path::Path::new(&file_path).exists() {
+        if repository.has_object(&object.hash) {
             continue;
         }
 
         count_down += 1;
     }
 
-    let (objects, _) = get_object_hashes("./.git/objects").await?;
+    let objects = repository.loose_objects()?;
 
     let flag_vec = contract.check_objects(objects.clone()).await?;
 
@@ -1,136 +1,1144 @@
-use crate::config::Config;
-use crate::git::head::update_current_files_to_current_head;
-use crate::utils::get_object_hashes;
+use crate::config::{Config, StorageBackend};
+use crate::contract_interaction::{ContractInteraction, Role};
+use crate::error::Error;
+use crate::git::filter::ObjectFilter;
+use crate::git::git_fs::check_if_object_exists;
+use crate::git::head::{get_head, resolve_head, update_current_files_to_current_head};
+use crate::git::objects::commit::CommitObject;
+use crate::git::objects::header::{ObjectHeader, ObjectType};
+use crate::git::objects::tree::TreeObject;
+use crate::git::refs::{validate_ref_name, Ref};
+use crate::git::refspec::{expand_ref_name, Refspec};
+use crate::git::repository::{is_object_hash, Repository};
+use crate::git::revision::is_ancestor;
+use crate::git::walk::parse_children;
+use crate::journal::PushJournal;
+use crate::remote::Remote;
+use crate::transaction::{read_in_flight, TransactionCost};
+use crate::utils::format_ether;
+use anyhow::bail;
 use colored::*;
-use ethcontract::jsonrpc::serde::__private::from_utf8_lossy;
 use ethcontract::U256;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::str::FromStr;
 
-pub async fn push() -> anyhow::Result<()> {
-    let contract_address = Config::get_contract_address();
+pub(crate) async fn resolve_remote(remote: Option<String>) -> anyhow::Result<Remote> {
+    let name = match remote {
+        Some(name) => name,
+        None => Remote::default_name().await?,
+    };
 
-    let count_up = sync_up(contract_address.clone()).await?;
+    Remote::get(&name)
+}
+
+// a refspec given without a destination fetches into the remote-tracking ref
+fn parse_fetch_refspec(remote: &Remote, refspec: &str) -> anyhow::Result<Refspec> {
+    if refspec.trim_start_matches('+').contains(':') {
+        return Refspec::from_str(refspec);
+    }
+
+    let force = refspec.starts_with('+');
+    let src = expand_ref_name(refspec.trim_start_matches('+'));
+    let dst = remote.tracking_ref(&src).unwrap_or(src.clone());
+
+    Ok(Refspec::new(force, &src, &dst))
+}
+
+/// The object hash stored in a contract ref.
+pub fn ref_value(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).trim().to_string()
+}
+
+/// The current value of every active ref on the contract.
+pub async fn get_remote_refs(
+    contract: &ContractInteraction,
+) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
+    Ok(contract
+        .get_active_refs()
+        .await?
+        .into_iter()
+        .map(|ref_| (ref_.name, ref_.data))
+        .collect())
+}
+
+/// The refs of the contract that may be written to a local repository,
+/// warning about the others: the contract takes any name and value, and a
+/// name like `../config` would point outside `refs/`.
+pub async fn get_fetchable_refs(
+    contract: &ContractInteraction,
+) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
+    let mut refs = get_remote_refs(contract).await?;
+
+    refs.retain(|name, data| {
+        let valid = name.starts_with("refs/")
+            && validate_ref_name(name).is_ok()
+            && is_object_hash(&ref_value(data));
+
+        if !valid {
+            eprintln!("warning: skipping invalid remote ref '{}'", name);
+        }
+
+        valid
+    });
+
+    Ok(refs)
+}
+
+/// The branch a repository contract's HEAD is taken to point at: `main`
+/// when it exists, otherwise the first branch.
+pub fn default_branch(refs: &BTreeMap<String, Vec<u8>>) -> Option<String> {
+    refs.keys()
+        .find(|name| *name == "refs/heads/main")
+        .or_else(|| refs.keys().find(|name| name.starts_with("refs/heads/")))
+        .cloned()
+}
+
+// how many objects are saved per `add_objects` transaction, to stay well
+// within the block gas limit
+const OBJECTS_PER_TRANSACTION: usize = 64;
+
+// a CIDv0, the length of what the storage backends return, to estimate
+// `add_objects` before anything is uploaded
+const PLACEHOLDER_IPFS_HASH: &str = "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG";
+
+/// What `upload_objects` did.
+#[derive(Debug, Default, Clone)]
+pub struct Upload {
+    /// How many objects were saved on the contract.
+    pub count: usize,
+    /// How many of them an interrupted push had already uploaded.
+    pub resumed: usize,
+    /// The transactions that saved them on the contract.
+    pub transactions: Vec<TransactionCost>,
+}
+
+/// The given objects that the contract doesn't know yet.
+pub async fn missing_objects(
+    contract: &ContractInteraction,
+    hashes: &[String],
+) -> anyhow::Result<Vec<String>> {
+    if hashes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let flag_vec = contract.check_objects(hashes.to_vec()).await?;
+
+    Ok(hashes
+        .iter()
+        .zip(flag_vec)
+        .filter(|(_, exists)| !exists)
+        .map(|(hash, _)| hash.clone())
+        .collect())
+}
+
+/// Upload the given objects of `repository` that the contract doesn't know yet to
+/// `storage` and save them on the contract with `add_objects`, a batch of
+/// objects per transaction. Uploads are journaled until they are saved, so
+/// the objects an interrupted push uploaded aren't uploaded again.
+pub async fn upload_objects(
+    contract: &ContractInteraction,
+    storage: StorageBackend,
+    repository: &Repository,
+    hashes: &[String],
+    on_upload: impl Fn(&str),
+) -> anyhow::Result<Upload> {
+    let mut journal = PushJournal::open(repository, &contract.address())?;
+    let mut upload = Upload::default();
+
+    let missing = missing_objects(contract, hashes).await?;
+
+    // saved by a push that was interrupted after its transaction was sent
+    let saved = hashes
+        .iter()
+        .filter(|hash| journal.get(hash).is_some() && !missing.contains(hash))
+        .cloned()
+        .collect::<Vec<String>>();
+    journal.remove(&saved)?;
+
+    for batch in missing.chunks(OBJECTS_PER_TRANSACTION) {
+        let mut ipfs_urls = Vec::new();
+
+        for hash in batch {
+            if let Some(cid) = journal.get(hash) {
+                ipfs_urls.push(cid.clone().into_bytes());
+                upload.resumed += 1;
+                continue;
+            }
+
+            let Some(path) = repository.loose_object_path(hash) else {
+                return Err(Error::InvalidObjectName(hash.to_string()).into());
+            };
+
+            // packed objects are uploaded in their loose form
+            if !path.is_file() {
+                repository.write_object(&repository.read_object(hash)?)?;
+            }
+
+            let ipfs_hash = crate::ipfs::load_to_storage(storage, &path.to_string_lossy()).await?;
+
+            journal.record(hash, &ipfs_hash)?;
+            ipfs_urls.push(ipfs_hash.into_bytes());
+
+            on_upload(hash);
+        }
+
+        upload
+            .transactions
+            .push(contract.add_objects(batch.to_vec(), ipfs_urls).await?);
+        upload.count += batch.len();
+
+        journal.remove(batch)?;
+    }
+
+    Ok(upload)
+}
+
+/// Print the gas used and fee paid per transaction, and their total.
+pub fn print_costs(transactions: &[TransactionCost]) {
+    if transactions.is_empty() {
+        return;
+    }
+
+    let mut gas_used = U256::zero();
+    let mut fee = U256::zero();
+
+    for transaction in transactions {
+        println!(
+            "  {} gas {} fee {} ETH",
+            transaction.hash,
+            transaction.gas_used,
+            format_ether(transaction.fee)
+        );
+
+        gas_used = gas_used + transaction.gas_used;
+        fee = fee + transaction.fee;
+    }
+
+    println!(
+        "{}",
+        format!(
+            "{} transactions, gas {} fee {} ETH",
+            transactions.len(),
+            gas_used,
+            format_ether(fee)
+        )
+        .green()
+    );
+}
+
+/// Download a single object, looked up on the contract by hash, into
+/// `objects/`.
+pub async fn download_object(
+    contract: &ContractInteraction,
+    repository: &Repository,
+    hash: &str,
+) -> anyhow::Result<()> {
+    let object = contract.get_object(hash.to_string()).await?;
+
+    if object.ipfs_url.is_empty() {
+        return Err(Error::ObjectNotFound(hash.to_string()).into());
+    }
+
+    download_verified(repository, hash, &object.ipfs_url).await
+}
+
+// download the object `hash` from storage and check that it is the object
+// it was saved as, so a wrong or tampered upload isn't stored under its hash
+async fn download_verified(
+    repository: &Repository,
+    hash: &str,
+    ipfs_url: &[u8],
+) -> anyhow::Result<()> {
+    let Some(path) = repository.loose_object_path(hash) else {
+        return Err(Error::InvalidObjectName(hash.to_string()).into());
+    };
+
+    crate::ipfs::download_from_ipfs(&String::from_utf8_lossy(ipfs_url), &path.to_string_lossy())
+        .await?;
+
+    repository.verify_loose_object(hash)?;
+
+    Ok(())
+}
+
+/// Download every object reachable from `tips` that is missing from the
+/// repository, looking each one up on the contract by hash.
+/// Returns how many were downloaded.
+pub async fn download_objects(
+    contract: &ContractInteraction,
+    repository: &Repository,
+    tips: &[String],
+    on_download: impl Fn(&str),
+) -> anyhow::Result<usize> {
+    download_filtered_objects(contract, repository, tips, None, on_download).await
+}
+
+// whether a blob passes the filter of a partial clone, by the size of its
+// stored (compressed) form, which is all that is known before downloading
+async fn blob_allowed(
+    contract: &ContractInteraction,
+    filter: ObjectFilter,
+    hash: &str,
+) -> anyhow::Result<bool> {
+    if filter == ObjectFilter::BlobNone {
+        return Ok(false);
+    }
+
+    let object = contract.get_object(hash.to_string()).await?;
+
+    Ok(
+        match crate::ipfs::object_size(&String::from_utf8_lossy(&object.ipfs_url)).await? {
+            Some(size) => filter.allows(size),
+            None => true,
+        },
+    )
+}
+
+/// [`download_objects`], leaving out the blobs `filter` rejects. They stay
+/// on the contract, to be fetched when something reads them.
+pub async fn download_filtered_objects(
+    contract: &ContractInteraction,
+    repository: &Repository,
+    tips: &[String],
+    filter: Option<ObjectFilter>,
+    on_download: impl Fn(&str),
+) -> anyhow::Result<usize> {
+    let mut seen = HashSet::new();
+    let mut blobs = HashSet::new();
+    let mut queue = tips.to_vec();
+    let mut count = 0;
+
+    while let Some(hash) = queue.pop() {
+        // an object present locally is assumed to have all its history
+        if !seen.insert(hash.clone()) || repository.has_object(&hash) {
+            continue;
+        }
+
+        if let (Some(filter), true) = (filter, blobs.contains(&hash)) {
+            if !blob_allowed(contract, filter, &hash).await? {
+                continue;
+            }
+        }
+
+        download_object(contract, repository, &hash).await?;
+
+        on_download(&hash);
+
+        let data = repository.read_object(&hash)?;
+
+        // tree entries are the only place the type of a blob shows up
+        // before it's downloaded
+        if filter.is_some() && ObjectHeader::try_from(data.clone())?.object_type == ObjectType::Tree
+        {
+            for entry in TreeObject::try_from(data.clone())?.entries {
+                if entry.object_type() == ObjectType::Blob {
+                    blobs.insert(entry.hash);
+                }
+            }
+        }
+
+        queue.extend(parse_children(data)?);
+
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Download the last `depth` commits of the history of `tips`, with their
+/// trees, and record the commits the history is cut off at in `shallow`.
+/// Shallow commits already in the repository are deepened from.
+/// Returns how many objects were downloaded.
+pub async fn download_shallow(
+    contract: &ContractInteraction,
+    repository: &Repository,
+    tips: &[String],
+    depth: usize,
+    filter: Option<ObjectFilter>,
+    on_download: impl Fn(&str),
+) -> anyhow::Result<usize> {
+    if depth == 0 {
+        bail!("Depth must be a positive number");
+    }
+
+    let mut shallow = repository.shallow_commits()?;
+    let mut seen = HashSet::new();
+    let mut count = 0;
+
+    let mut level = tips.to_vec();
+
+    for current_depth in 1..=depth {
+        let mut next = Vec::new();
+
+        while let Some(hash) = level.pop() {
+            if !seen.insert(hash.clone()) {
+                continue;
+            }
+
+            // complete history, nothing to deepen
+            if repository.has_object(&hash) && !shallow.contains(&hash) {
+                continue;
+            }
+
+            if !repository.has_object(&hash) {
+                download_object(contract, repository, &hash).await?;
+                on_download(&hash);
+                count += 1;
+            }
+
+            let data = repository.read_object(&hash)?;
+
+            match ObjectHeader::try_from(data.clone())?.object_type {
+                ObjectType::Commit => {
+                    let commit = CommitObject::try_from(data)?;
+
+                    count += download_filtered_objects(
+                        contract,
+                        repository,
+                        std::slice::from_ref(&commit.content.tree_sha),
+                        filter,
+                        &on_download,
+                    )
+                    .await?;
+
+                    let cut_off = current_depth == depth
+                        && commit
+                            .content
+                            .parent_sha
+                            .iter()
+                            .any(|parent| !repository.has_object(parent));
+
+                    if cut_off {
+                        shallow.insert(hash);
+                    } else {
+                        shallow.remove(&hash);
+                        next.extend(commit.content.parent_sha);
+                    }
+                }
+                // a tag counts as the commit it points at
+                ObjectType::Tag => level.extend(parse_children(data)?),
+                _ => {
+                    let children = parse_children(data)?;
+
+                    count += download_filtered_objects(
+                        contract,
+                        repository,
+                        &children,
+                        filter,
+                        &on_download,
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        level = next;
+    }
+
+    repository.set_shallow_commits(&shallow)?;
+
+    Ok(count)
+}
+
+/// Download the missing history behind every shallow commit and remove
+/// `shallow`. Returns how many objects were downloaded.
+pub async fn unshallow(
+    contract: &ContractInteraction,
+    repository: &Repository,
+    filter: Option<ObjectFilter>,
+    on_download: impl Fn(&str),
+) -> anyhow::Result<usize> {
+    let mut count = 0;
+
+    for hash in repository.shallow_commits()? {
+        let parents = CommitObject::try_from(repository.read_object(&hash)?)?
+            .content
+            .parent_sha;
+
+        count +=
+            download_filtered_objects(contract, repository, &parents, filter, &on_download).await?;
+    }
+
+    repository.set_shallow_commits(&BTreeSet::new())?;
+
+    Ok(count)
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PushOptions {
+    /// Only estimate the gas and cost of the push, without uploading or
+    /// sending anything.
+    pub dry_run: bool,
+    /// Show what an interrupted push left pending instead of pushing.
+    pub status: bool,
+    /// Delete the given refs from the contract instead of pushing them.
+    pub delete: bool,
+}
+
+// `push --delete`: mark the refs inactive on the contract and drop their
+// remote-tracking refs
+async fn delete_remote_refs(
+    remote: &Remote,
+    names: &[String],
+    options: &PushOptions,
+) -> anyhow::Result<()> {
+    if names.is_empty() {
+        bail!("No refs to delete given");
+    }
+
+    let names = names
+        .iter()
+        .map(|name| expand_ref_name(name))
+        .collect::<Vec<String>>();
+
+    let contract = ContractInteraction::for_remote(remote)?;
+
+    contract.require_role(Role::Writer).await?;
+
+    let pusher = contract.sender_address().await?;
+    let remote_refs = get_remote_refs(&contract).await?;
+    let mut old = Vec::new();
+
+    for name in &names {
+        let Some(data) = remote_refs.get(name) else {
+            bail!("Unable to delete {}: remote ref does not exist", name);
+        };
+
+        old.push(data.clone());
+
+        if let Some(protection) = contract.get_protection(name.clone()).await? {
+            protection.check_deletion(name, pusher)?;
+        }
+    }
+
+    if options.dry_run {
+        let gas = contract
+            .estimate_delete_refs(names.clone(), old.clone())
+            .await?;
+        let gas_price = contract.gas_price().await?;
+
+        for name in &names {
+            println!("Would delete ref: {}", name);
+        }
+
+        println!(
+            "Estimated gas: {} in 1 transactions at {} wei per gas",
+            gas, gas_price
+        );
+        println!(
+            "{}",
+            format!("Estimated cost: {} ETH", format_ether(gas * gas_price)).green()
+        );
+
+        return Ok(());
+    }
+
+    let cost = contract.delete_refs(names.clone(), old).await?;
+
+    for name in &names {
+        if let Some(tracking_ref) = remote.tracking_ref(name) {
+            Ref::new(&tracking_ref).delete().await?;
+        }
+
+        println!("{}", format!("Deleted ref: {}", name).cyan());
+    }
+
+    print_costs(&[cost]);
+
+    Ok(())
+}
+
+// the uploads and transactions an interrupted push left, from the local
+// journals only
+fn push_status(remote: &Remote) -> anyhow::Result<()> {
+    let repository = Repository::current()?;
+    let journal = PushJournal::open(&repository, &remote.contract_address)?;
+    let in_flight = read_in_flight()?;
+
+    println!("Push to {} ({})", remote.name, remote.contract_address);
+
+    if journal.uploads().is_empty() && in_flight.is_empty() {
+        println!("{}", "Nothing pending".green());
+        return Ok(());
+    }
+
+    if !journal.uploads().is_empty() {
+        println!(
+            "{}",
+            format!(
+                "Objects uploaded but not saved on the contract: {}",
+                journal.uploads().len()
+            )
+            .yellow()
+        );
+
+        for (hash, cid) in journal.uploads() {
+            println!("  {} {}", hash, cid);
+        }
+    }
+
+    if !in_flight.is_empty() {
+        println!(
+            "{}",
+            format!("Transactions waiting to be mined: {}", in_flight.len()).yellow()
+        );
 
-    println!("{}", format!("Synced up: {}", count_up).green());
+        for entry in &in_flight {
+            println!("  {:?} (nonce {})", entry.hash, entry.nonce);
+        }
+    }
+
+    println!("Run push again to resume");
+
+    Ok(())
+}
+
+pub async fn push(
+    remote: Option<String>,
+    refspecs: Vec<String>,
+    options: &PushOptions,
+) -> anyhow::Result<()> {
+    let remote = resolve_remote(remote).await?;
+
+    if options.status {
+        return push_status(&remote);
+    }
+
+    if options.delete {
+        return delete_remote_refs(&remote, &refspecs, options).await;
+    }
+
+    let refspecs = if refspecs.is_empty() {
+        remote.push.clone()
+    } else {
+        refspecs
+            .iter()
+            .map(|refspec| Refspec::from_str(refspec))
+            .collect::<anyhow::Result<Vec<Refspec>>>()?
+    };
+
+    let count_up = sync_up(&remote, &refspecs, options).await?;
+
+    if !options.dry_run {
+        println!("{}", format!("Synced up: {}", count_up).green());
+    }
 
     Ok(())
 }
 
-pub async fn pull() -> anyhow::Result<()> {
-    let contract_address = Config::get_contract_address();
+#[derive(Debug, Default, Clone)]
+pub struct FetchOptions {
+    /// Extend the history of a shallow repository by this many commits.
+    pub deepen: Option<usize>,
+    /// Download the complete history of a shallow repository.
+    pub unshallow: bool,
+    /// Delete the remote-tracking refs whose ref was deleted on the contract.
+    pub prune: bool,
+}
+
+/// Delete the local refs `refspecs` fetch into whose remote ref isn't active
+/// on the contract anymore. How many were deleted.
+pub async fn prune_refs(
+    contract: &ContractInteraction,
+    refspecs: &[Refspec],
+) -> anyhow::Result<usize> {
+    let remote_refs = get_remote_refs(contract).await?;
+    let mut count = 0;
+
+    for (name, _) in Ref::get_all_refs().await? {
+        let Some(remote_ref) = refspecs
+            .iter()
+            .find_map(|refspec| refspec.map_reverse(&name))
+        else {
+            continue;
+        };
 
-    let count_down = sync_down(contract_address.clone()).await?;
+        if remote_refs.contains_key(&remote_ref) {
+            continue;
+        }
+
+        Ref::new(&name).delete().await?;
+
+        println!(
+            "{}",
+            format!("Pruned {}: {} was deleted on the remote", name, remote_ref).yellow()
+        );
+
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+pub async fn fetch(
+    remote: Option<String>,
+    refspecs: Vec<String>,
+    options: &FetchOptions,
+) -> anyhow::Result<()> {
+    let remote = resolve_remote(remote).await?;
+
+    let refspecs = if refspecs.is_empty() {
+        remote.fetch.clone()
+    } else {
+        refspecs
+            .iter()
+            .map(|refspec| parse_fetch_refspec(&remote, refspec))
+            .collect::<anyhow::Result<Vec<Refspec>>>()?
+    };
+
+    if options.deepen.is_some() || options.unshallow {
+        let repository = Repository::current()?;
+
+        if !repository.is_shallow() {
+            bail!("--deepen and --unshallow only apply to a shallow repository");
+        }
+
+        let contract = ContractInteraction::for_remote(&remote)?;
+        let on_download = |hash: &str| {
+            println!("{}", format!("Downloaded object: {}", hash).cyan());
+        };
+
+        let count = match options.deepen {
+            // the shallow commits themselves are the first level
+            Some(deepen) => {
+                let tips = repository
+                    .shallow_commits()?
+                    .into_iter()
+                    .collect::<Vec<String>>();
+
+                download_shallow(
+                    &contract,
+                    &repository,
+                    &tips,
+                    deepen + 1,
+                    remote.filter,
+                    on_download,
+                )
+                .await?
+            }
+            None => unshallow(&contract, &repository, remote.filter, on_download).await?,
+        };
+
+        println!("{}", format!("Deepened history: {}", count).blue());
+    }
+
+    let count_down = sync_down(&remote, &refspecs, None).await?;
 
     println!("{}", format!("Synced down: {}", count_down).blue());
 
+    if options.prune {
+        let contract = ContractInteraction::for_remote(&remote)?;
+
+        prune_refs(&contract, &refspecs).await?;
+    }
+
     Ok(())
 }
 
-pub async fn sync_up(contract_address: String) -> anyhow::Result<usize> {
-    let (objects, paths) = get_object_hashes("./.git/objects").await?;
+pub async fn pull(remote: Option<String>, refspecs: Vec<String>) -> anyhow::Result<()> {
+    let remote = resolve_remote(remote).await?;
+
+    let head = get_head().await?;
+
+    let Some(branch) = head.strip_prefix("refs/heads/") else {
+        bail!("You are not currently on a branch");
+    };
+
+    // the remote branch to integrate: the first refspec, the configured
+    // upstream, or the branch of the same name
+    let merge_ref = match refspecs.first() {
+        Some(refspec) => parse_fetch_refspec(&remote, refspec)?.src,
+        None => crate::git::config::Config::load()?
+            .get(&format!("branch.{}.merge", branch))
+            .unwrap_or(head.clone()),
+    };
+
+    let mut fetch_refspecs = remote.fetch.clone();
+
+    for refspec in &refspecs {
+        fetch_refspecs.push(parse_fetch_refspec(&remote, refspec)?);
+    }
+
+    let count_down = sync_down(&remote, &fetch_refspecs, None).await?;
+
+    println!("{}", format!("Synced down: {}", count_down).blue());
+
+    let Some(tracking_ref) = remote.tracking_ref(&merge_ref) else {
+        bail!(
+            "{} is not fetched by any refspec of {}",
+            merge_ref,
+            remote.name
+        );
+    };
 
-    let contract =
-        crate::contract_interaction::ContractInteraction::new_with_address(&contract_address);
+    let theirs = Ref::new(&tracking_ref).resolve().await?;
+
+    if theirs.is_empty() {
+        bail!("Couldn't find remote ref {}", merge_ref);
+    }
+
+    let ours = resolve_head().await?;
+
+    if ours == theirs || (!ours.is_empty() && is_ancestor(&theirs, &ours)?) {
+        println!("{}", "Already up to date".blue());
+        return Ok(());
+    }
+
+    if !ours.is_empty() && !is_ancestor(&ours, &theirs)? {
+        bail!(
+            "{} and {} have diverged, cannot fast-forward",
+            head,
+            tracking_ref
+        );
+    }
+
+    Ref::new(&head)
+        .update(&theirs, "pull: Fast-forward")
+        .await?;
+
+    update_current_files_to_current_head().await?;
 
     println!(
         "{}",
-        format!("Using contract address: {}", contract_address).yellow()
+        format!("Fast-forwarded {} to {}", branch, theirs).blue()
     );
 
-    let flag_vec = contract.check_objects(objects.clone()).await?;
+    Ok(())
+}
+
+// a ref update a push makes
+struct RefUpdate {
+    src: String,
+    dst: String,
+    /// The value on the contract the update replaces, empty for a new ref.
+    old: Vec<u8>,
+    data: Vec<u8>,
+    /// Not a fast-forward, sent with `force_add_refs`.
+    forced: bool,
+}
+
+// the names, old and new values of `updates` that are forced or not
+fn split_updates(updates: &[RefUpdate], forced: bool) -> (Vec<String>, Vec<Vec<u8>>, Vec<Vec<u8>>) {
+    let updates = updates.iter().filter(|update| update.forced == forced);
+
+    (
+        updates.clone().map(|update| update.dst.clone()).collect(),
+        updates.clone().map(|update| update.old.clone()).collect(),
+        updates.map(|update| update.data.clone()).collect(),
+    )
+}
 
-    let mut ipfs_hashes = Vec::new();
+// what a push of `objects` and `updates` would cost at the current gas
+// price, printed instead of pushing
+async fn estimate_push(
+    contract: &ContractInteraction,
+    objects: &[String],
+    updates: &[RefUpdate],
+) -> anyhow::Result<()> {
+    let mut gas = U256::zero();
+    let mut transactions = 0;
 
-    for (i, flag) in flag_vec.iter().enumerate() {
-        if !flag {
-            let ipfs_hash = crate::ipfs::load_to_ipfs(paths[i].as_str()).await?;
-            ipfs_hashes.push(ipfs_hash.clone().into_bytes());
+    for batch in objects.chunks(OBJECTS_PER_TRANSACTION) {
+        let ipfs_urls = vec![PLACEHOLDER_IPFS_HASH.as_bytes().to_vec(); batch.len()];
 
-            contract
-                .save_object(objects[i].clone(), ipfs_hash.into_bytes())
+        gas = gas
+            + contract
+                .estimate_add_objects(batch.to_vec(), ipfs_urls)
                 .await?;
+        transactions += 1;
+    }
 
-            println!(
-                "{}",
-                format!("Uploaded and saved object: {}", objects[i]).cyan()
-            );
-        }
+    let (names, old, data) = split_updates(updates, false);
+
+    if !names.is_empty() {
+        gas = gas + contract.estimate_add_refs(names, old, data).await?;
+        transactions += 1;
     }
 
-    // get all refs and push them to the contract
-    let refs = crate::git::refs::Ref::get_all_refs().await?;
+    let (names, old, data) = split_updates(updates, true);
+
+    if !names.is_empty() {
+        gas = gas + contract.estimate_force_add_refs(names, old, data).await?;
+        transactions += 1;
+    }
 
-    for (ref_name, ref_data) in refs {
-        contract.add_ref(ref_name.clone(), ref_data).await?;
-        println!("{}", format!("Uploaded and saved ref: {}", ref_name).cyan());
+    for update in updates {
+        println!("Would update ref: {} -> {}", update.src, update.dst);
     }
 
+    let gas_price = contract.gas_price().await?;
+
+    println!("Objects to upload: {}", objects.len());
+    println!("Refs to update: {}", updates.len());
+    println!(
+        "Estimated gas: {} in {} transactions at {} wei per gas",
+        gas, transactions, gas_price
+    );
     println!(
         "{}",
-        format!("Total objects synced up: {}", ipfs_hashes.len()).green()
+        format!("Estimated cost: {} ETH", format_ether(gas * gas_price)).green()
     );
-    Ok(ipfs_hashes.len())
-}
 
-pub async fn sync_down(contract_address: String) -> anyhow::Result<usize> {
-    let contract =
-        crate::contract_interaction::ContractInteraction::new_with_address(&contract_address);
+    Ok(())
+}
 
-    let mut count = 0usize;
+pub async fn sync_up(
+    remote: &Remote,
+    refspecs: &[Refspec],
+    options: &PushOptions,
+) -> anyhow::Result<usize> {
+    let repository = Repository::current()?;
+    let objects = repository.loose_objects()?;
 
-    let len_of_objects = contract.get_objects_length().await?.as_u64();
+    let contract = ContractInteraction::for_remote(remote)?;
+    let storage = match remote.storage {
+        Some(storage) => storage,
+        None => Config::storage_backend()?,
+    };
 
     println!(
         "{}",
-        format!("Length of objects in contract: {}", len_of_objects).yellow()
+        format!("Using remote {} ({})", remote.name, remote.contract_address).yellow()
     );
 
-    for index in 0..len_of_objects {
-        let object = contract.get_object_by_id(U256::from(index)).await?;
-        let ipfs_hash = object.ipfs_url;
-        let (prefix, hash) = object.hash.split_at(2);
-        let file_path = format!("./.git/objects/{}/{}", prefix, hash);
+    // a push without write access would only revert
+    contract.require_role(Role::Writer).await?;
+
+    let pusher = contract.sender_address().await?;
+    let remote_refs = get_remote_refs(&contract).await?;
 
-        if std::path::Path::new(&file_path).exists() {
-            println!("{}", format!("File already exists: {}", file_path).blue());
+    // check every ref update before paying for any transaction
+    let mut updates = Vec::new();
+
+    for (ref_name, ref_data) in Ref::get_all_refs().await? {
+        let Some((refspec, dst)) = refspecs
+            .iter()
+            .find_map(|refspec| Some((refspec, refspec.map(&ref_name)?)))
+        else {
             continue;
+        };
+
+        let new = ref_value(&ref_data);
+        let mut forced = false;
+
+        if let Some(old) = remote_refs.get(&dst).map(|data| ref_value(data)) {
+            if old == new {
+                continue;
+            }
+
+            if !check_if_object_exists(&old) {
+                if !refspec.force {
+                    bail!(
+                        "Updates to {} were rejected: the remote contains work you do not have locally, fetch first",
+                        dst
+                    );
+                }
+
+                forced = true;
+            } else if !is_ancestor(&old, &new)? {
+                if !refspec.force {
+                    bail!(
+                        "Updates to {} were rejected: non-fast-forward, pull first or force with +{}",
+                        dst,
+                        ref_name
+                    );
+                }
+
+                forced = true;
+            }
         }
 
-        crate::ipfs::download_from_ipfs(from_utf8_lossy(ipfs_hash.as_slice()).as_ref(), &file_path)
-            .await?;
+        if let Some(protection) = contract.get_protection(dst.clone()).await? {
+            protection.check_update(&dst, pusher, forced)?;
+        }
+
+        updates.push(RefUpdate {
+            src: ref_name,
+            old: remote_refs.get(&dst).cloned().unwrap_or_default(),
+            dst,
+            data: ref_data,
+            forced,
+        });
+    }
+
+    if options.dry_run {
+        let missing = missing_objects(&contract, &objects).await?;
+
+        estimate_push(&contract, &missing, &updates).await?;
 
+        return Ok(0);
+    }
+
+    let mut upload = upload_objects(&contract, storage, &repository, &objects, |hash| {
+        println!("{}", format!("Uploaded object: {}", hash).cyan());
+    })
+    .await?;
+
+    if upload.resumed > 0 {
         println!(
             "{}",
-            format!("Downloaded and saved file: {}", file_path).cyan()
+            format!(
+                "Resumed {} objects uploaded by an interrupted push",
+                upload.resumed
+            )
+            .yellow()
         );
+    }
 
-        count += 1;
+    let (names, old, data) = split_updates(&updates, false);
+
+    if !names.is_empty() {
+        upload
+            .transactions
+            .push(contract.add_refs(names, old, data).await?);
+    }
+
+    let (names, old, data) = split_updates(&updates, true);
+
+    if !names.is_empty() {
+        upload
+            .transactions
+            .push(contract.force_add_refs(names, old, data).await?);
+    }
+
+    for update in updates {
+        if let Some(tracking_ref) = remote.tracking_ref(&update.dst) {
+            Ref::new(&tracking_ref)
+                .update(&ref_value(&update.data), "update by push")
+                .await?;
+        }
+
+        println!(
+            "{}",
+            format!("Uploaded and saved ref: {} -> {}", update.src, update.dst).cyan()
+        );
     }
 
-    let ref_count = contract.get_refs_length().await?.as_u64();
+    print_costs(&upload.transactions);
+
+    println!(
+        "{}",
+        format!("Total objects synced up: {}", upload.count).green()
+    );
+    Ok(upload.count)
+}
+
+/// Fetch the refs `refspecs` map from the contract of `remote`, with
+/// their objects: every object on the contract, or only the history of the
+/// fetched refs in a shallow repository or partial clone, cut off at
+/// `depth` commits if given.
+pub async fn sync_down(
+    remote: &Remote,
+    refspecs: &[Refspec],
+    depth: Option<usize>,
+) -> anyhow::Result<usize> {
+    let contract = ContractInteraction::for_remote(remote)?;
+    let repository = Repository::current()?;
+
+    // local name, new value and whether it may be forced, per fetched ref
+    let mut updates = Vec::new();
+
+    for (ref_name, ref_data) in get_fetchable_refs(&contract).await? {
+        let Some((refspec, local_name)) = refspecs
+            .iter()
+            .find_map(|refspec| Some((refspec, refspec.map(&ref_name)?)))
+        else {
+            continue;
+        };
+
+        updates.push((local_name, ref_value(&ref_data), refspec.force));
+    }
+
+    let mut count = 0usize;
+
+    if depth.is_some() || remote.filter.is_some() || repository.is_shallow() {
+        let tips = updates
+            .iter()
+            .map(|(_, new, _)| new.clone())
+            .collect::<Vec<String>>();
+
+        let on_download = |hash: &str| {
+            println!("{}", format!("Downloaded object: {}", hash).cyan());
+        };
+
+        count = match depth {
+            Some(depth) => {
+                download_shallow(
+                    &contract,
+                    &repository,
+                    &tips,
+                    depth,
+                    remote.filter,
+                    on_download,
+                )
+                .await?
+            }
+            None => {
+                download_filtered_objects(&contract, &repository, &tips, remote.filter, on_download)
+                    .await?
+            }
+        };
+    } else {
+        let len_of_objects = contract.get_objects_length().await?.as_u64();
+
+        println!(
+            "{}",
+            format!("Length of objects in contract: {}", len_of_objects).yellow()
+        );
+
+        for index in 0..len_of_objects {
+            let object = contract.get_object_by_id(U256::from(index)).await?;
+            let Some(file_path) = repository.loose_object_path(&object.hash) else {
+                return Err(Error::InvalidObjectName(object.hash).into());
+            };
+            let file_path = file_path.to_string_lossy().to_string();
+
+            if repository.has_object(&object.hash) {
+                println!("{}", format!("File already exists: {}", file_path).blue());
+                continue;
+            }
+
+            download_verified(&repository, &object.hash, &object.ipfs_url).await?;
+
+            println!(
+                "{}",
+                format!("Downloaded and saved file: {}", file_path).cyan()
+            );
+
+            count += 1;
+        }
+    }
 
     let mut ref_count_updated = 0;
 
-    for index in 0..ref_count {
-        let ref_ = contract.get_ref_by_id(U256::from(index)).await?;
-        let ref_name = ref_.name;
-        let ref_data = ref_.data;
+    for (local_name, new, force) in updates {
+        let old = Ref::new(&local_name).resolve().await.unwrap_or_default();
 
-        // check if ref exists
-        let path = format!("./.git/{}", ref_name);
-        let content = tokio::fs::read(path).await.unwrap_or(vec![]);
+        if old == new {
+            continue;
+        }
 
-        if content != ref_data {
-            ref_count_updated += 1;
+        let fast_forward = old.is_empty() || is_ancestor(&old, &new)?;
+
+        if !force && !fast_forward {
+            println!(
+                "{}",
+                format!("Rejected non-fast-forward update of {}", local_name).red()
+            );
+            continue;
         }
 
-        // save ref to disk
-        let path = format!("./.git/{}", ref_name);
+        let message = if old.is_empty() {
+            "fetch: storing head"
+        } else if fast_forward {
+            "fetch: fast-forward"
+        } else {
+            "fetch: forced-update"
+        };
 
-        // create parent directories if they don't exist
-        let parent = std::path::Path::new(&path).parent().unwrap();
-        let _ = tokio::fs::create_dir_all(parent).await;
+        Ref::new(&local_name).update(&new, message).await?;
 
-        tokio::fs::write(path, ref_data).await?;
+        ref_count_updated += 1;
     }
 
     println!("{}", format!("Total objects synced down: {}", count).blue());
@@ -139,7 +1147,5 @@ pub async fn sync_down(contract_address: String) -> anyhow::Result<usize> {
         format!("Total refs synced down: {}", ref_count_updated).blue()
     );
 
-    update_current_files_to_current_head().await?;
-
     Ok(count)
 }
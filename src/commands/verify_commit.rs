@@ -0,0 +1,136 @@
+use crate::contract_interaction::{address_to_string, ContractInteraction};
+use crate::git::git_fs::get_commit_object;
+use crate::git::objects::commit::CommitObject;
+use crate::git::revision::resolve_revision;
+use crate::remote::Remote;
+use crate::signature::recover_signer;
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+/// What the signature of a commit says about who made it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignatureStatus {
+    /// The commit has no `gpgsig` header.
+    Unsigned,
+    /// No address could be recovered from the signature.
+    Bad(String),
+    /// The signature recovers to `signer`; `pusher` is the account that
+    /// pushed the commit to the remote, if it was pushed.
+    Good {
+        signer: String,
+        pusher: Option<String>,
+    },
+}
+
+impl SignatureStatus {
+    /// Whether the commit was signed by the account that pushed it.
+    pub fn is_trusted(&self) -> bool {
+        match self {
+            SignatureStatus::Good {
+                signer,
+                pusher: Some(pusher),
+            } => signer == pusher,
+            _ => false,
+        }
+    }
+
+    /// Print the status like `git log --show-signature` does.
+    pub fn print(&self) {
+        match self {
+            SignatureStatus::Unsigned => {}
+            SignatureStatus::Bad(reason) => {
+                println!("{}", format!("Bad signature: {}", reason).red())
+            }
+            SignatureStatus::Good { signer, pusher } => match pusher {
+                Some(pusher) if pusher == signer => {
+                    println!("{}", format!("Good signature from {}", signer).green())
+                }
+                Some(pusher) => println!(
+                    "{}",
+                    format!(
+                        "Signature from {} does not match the pusher {}",
+                        signer, pusher
+                    )
+                    .red()
+                ),
+                None => println!(
+                    "{}",
+                    format!("Good signature from {} (not pushed)", signer).yellow()
+                ),
+            },
+        }
+    }
+}
+
+/// The contract of the default remote, when there is one to compare
+/// signers with.
+pub async fn signature_contract() -> Result<Option<ContractInteraction>> {
+    let Ok(remote) = Remote::get(&Remote::default_name().await?) else {
+        return Ok(None);
+    };
+
+    Ok(Some(ContractInteraction::for_remote(&remote)?))
+}
+
+/// Recover the signer of a commit and look up who pushed it to `contract`.
+pub async fn check_signature(
+    hash: &str,
+    commit: &CommitObject,
+    contract: Option<&ContractInteraction>,
+) -> Result<SignatureStatus> {
+    let Some(signature) = &commit.content.signature else {
+        return Ok(SignatureStatus::Unsigned);
+    };
+
+    let signer = match recover_signer(&commit.content.payload(), signature) {
+        Ok(signer) => address_to_string(&signer),
+        Err(e) => return Ok(SignatureStatus::Bad(e.to_string())),
+    };
+
+    let pusher = match contract {
+        Some(contract) if contract.is_object_exist(hash.to_string()).await? => Some(
+            address_to_string(&contract.get_object(hash.to_string()).await?.pusher),
+        ),
+        _ => None,
+    };
+
+    Ok(SignatureStatus::Good { signer, pusher })
+}
+
+/// Check the signature of each commit against the account that pushed it,
+/// failing unless all of them are good.
+pub async fn verify_commit(commits: &[String]) -> Result<()> {
+    let contract = signature_contract().await?;
+
+    let mut failed = 0;
+
+    for commit in commits {
+        let hash = resolve_revision(commit).await?;
+        let status = check_signature(&hash, &get_commit_object(&hash)?, contract.as_ref()).await?;
+
+        match &status {
+            SignatureStatus::Unsigned => {
+                eprintln!("{}", format!("No signature on commit {}", hash).red());
+                failed += 1;
+            }
+            SignatureStatus::Good { pusher: None, .. } => status.print(),
+            _ => {
+                status.print();
+
+                if !status.is_trusted() {
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "{} of {} commits failed verification",
+            failed,
+            commits.len()
+        );
+    }
+
+    Ok(())
+}
@@ -1,31 +1,186 @@
+use crate::error::{Error, Result};
 use crate::git;
+use crate::remote::{Remote, DEFAULT_REMOTE};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub const DEFAULT_RPC_URL: &str = "http://localhost:8545";
+pub const DEFAULT_IPFS_API_URL: &str = "http://127.0.0.1:5001";
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// Where object contents are uploaded to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageBackend {
+    /// Pin files through the Pinata API.
+    Pinata,
+    /// Add files through the HTTP API of an IPFS (Kubo) node.
+    Ipfs,
+}
+
+impl FromStr for StorageBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "pinata" => Ok(StorageBackend::Pinata),
+            "ipfs" => Ok(StorageBackend::Ipfs),
+            _ => Err(Error::Config(format!(
+                "Unknown storage backend '{}', expected pinata or ipfs",
+                s
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageBackend::Pinata => write!(f, "pinata"),
+            StorageBackend::Ipfs => write!(f, "ipfs"),
+        }
+    }
+}
+
+fn load_config() -> Result<git::config::Config> {
+    git::config::Config::load().map_err(|e| Error::Config(e.to_string()))
+}
 
 pub struct Config;
 
 impl Config {
-    pub fn get_contract_address() -> String {
-        let config = git::config::Config::get();
+    // the `[dgit]` config value, falling back to an environment variable
+    fn get_value(key: &str, env: &str) -> Result<Option<String>> {
+        let config = load_config()?;
+
+        Ok(config.get(key).or_else(|| dotenv::var(env).ok()))
+    }
+
+    /// The contract address of the `origin` remote.
+    pub fn get_contract_address() -> Result<String> {
+        Remote::get(DEFAULT_REMOTE)
+            .map(|remote| remote.contract_address)
+            .map_err(|e| Error::Config(e.to_string()))
+    }
+
+    pub fn get_pinata_secret_api_key() -> Result<String> {
+        Self::get_value("dgit.pinataSecretApiKey", "PINATA_SECRET_API_KEY")?
+            .ok_or_else(|| Error::Config("PINATA_SECRET_API_KEY is not set".to_string()))
+    }
+
+    pub fn get_pinata_api_key() -> Result<String> {
+        Self::get_value("dgit.pinataApiKey", "PINATA_API_KEY")?
+            .ok_or_else(|| Error::Config("PINATA_API_KEY is not set".to_string()))
+    }
+
+    pub fn pk() -> Result<String> {
+        dotenv::var("PK").map_err(|_| Error::Config("PK is not set".to_string()))
+    }
+
+    /// The keystore to sign with, a file or the address of an account in
+    /// the keystore directory.
+    pub fn keystore() -> Result<Option<String>> {
+        Self::get_value("dgit.keystore", "DGIT_KEYSTORE")
+    }
+
+    /// Where `dgit account` keeps keystores, `~/.dgit/keystore` by default.
+    pub fn keystore_dir() -> Result<PathBuf> {
+        if let Some(dir) = Self::get_value("dgit.keystoreDir", "DGIT_KEYSTORE_DIR")? {
+            return Ok(PathBuf::from(dir));
+        }
+
+        std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .map(|home| Path::new(&home).join(".dgit").join("keystore"))
+            .map_err(|_| Error::Config("No home directory for the keystore".to_string()))
+    }
+
+    /// The BIP-39 mnemonic to derive the key from, only read from the
+    /// environment like `PK`.
+    pub fn mnemonic() -> Option<String> {
+        dotenv::var("MNEMONIC").ok()
+    }
+
+    pub fn derivation_path() -> Result<String> {
+        Ok(Self::get_value("dgit.derivationPath", "DERIVATION_PATH")?
+            .unwrap_or(DEFAULT_DERIVATION_PATH.to_string()))
+    }
+
+    /// The keystore password, prompted for when not in the environment.
+    pub fn keystore_password() -> Option<String> {
+        dotenv::var("DGIT_PASSWORD").ok()
+    }
+
+    pub fn rpc_url() -> Result<String> {
+        Ok(Self::get_value("dgit.rpcUrl", "RPC_URL")?.unwrap_or(DEFAULT_RPC_URL.to_string()))
+    }
 
-        config.repository_address
+    pub fn chain_id() -> Result<Option<u64>> {
+        Self::get_number("dgit.chainId", "CHAIN_ID")
     }
 
-    pub fn get_pinata_secret_api_key() -> String {
-        dotenv::var("PINATA_SECRET_API_KEY").unwrap()
+    // a numeric `[dgit]` config value
+    fn get_number<T: FromStr>(key: &str, env: &str) -> Result<Option<T>> {
+        Self::get_value(key, env)?
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| Error::Config(format!("Invalid {}: {}", key, value)))
+            })
+            .transpose()
     }
 
-    pub fn get_pinata_api_key() -> String {
-        dotenv::var("PINATA_API_KEY").unwrap()
+    /// How many blocks a transaction must be in before it counts as
+    /// confirmed, the block it was mined in included.
+    pub fn confirmations() -> Result<usize> {
+        Ok(Self::get_number("dgit.confirmations", "DGIT_CONFIRMATIONS")?.unwrap_or(1))
     }
 
-    pub fn pk() -> String {
-        dotenv::var("PK").unwrap()
+    /// How many seconds to wait for a transaction to be mined before
+    /// replacing it with a higher fee.
+    pub fn transaction_timeout() -> Result<u64> {
+        Ok(Self::get_number("dgit.txTimeout", "DGIT_TX_TIMEOUT")?.unwrap_or(120))
     }
 
-    pub fn rpc_url() -> String {
-        dotenv::var("RPC_URL").unwrap()
+    /// The percentage the gas price of a replacement transaction is raised by.
+    pub fn fee_bump() -> Result<u64> {
+        Ok(Self::get_number("dgit.feeBump", "DGIT_FEE_BUMP")?.unwrap_or(15))
+    }
+
+    /// How many times a transaction is sent, replacements included.
+    pub fn transaction_attempts() -> Result<usize> {
+        Ok(Self::get_number("dgit.txAttempts", "DGIT_TX_ATTEMPTS")?.unwrap_or(4))
+    }
+
+    pub fn storage_backend() -> Result<StorageBackend> {
+        Self::get_value("dgit.storage", "DGIT_STORAGE")?
+            .map(|backend| StorageBackend::from_str(&backend))
+            .unwrap_or(Ok(StorageBackend::Pinata))
+    }
+
+    pub fn ipfs_api_url() -> Result<String> {
+        Ok(Self::get_value("dgit.ipfsApi", "IPFS_API_URL")?
+            .unwrap_or(DEFAULT_IPFS_API_URL.to_string()))
+    }
+
+    /// IPFS gateway prefixes to download from, in order of preference.
+    pub fn gateways() -> Result<Vec<String>> {
+        let mut gateways = load_config()?.get_all("dgit.gateway");
+
+        if let Ok(prefix) = dotenv::var("IPFS_PREFIX") {
+            gateways.push(prefix);
+        }
+
+        if gateways.is_empty() {
+            return Err(Error::Config(
+                "No IPFS gateway configured, add dgit.gateway with `dgit config` or set IPFS_PREFIX"
+                    .to_string(),
+            ));
+        }
+
+        Ok(gateways)
     }
 
-    pub fn ipfs_prefix() -> String {
-        dotenv::var("IPFS_PREFIX").unwrap()
+    pub fn ipfs_prefix() -> Result<String> {
+        Ok(Self::gateways()?.remove(0))
     }
 }
@@ -1,13 +1,25 @@
 use crate::config::Config;
-use anyhow::Result;
+use crate::error::{Error, Result};
+use crate::remote::Remote;
+use crate::signer::signer_key;
+use crate::transaction::{TransactionCost, TransactionManager};
+use ethcontract::dyns::DynTransport;
 use ethcontract::prelude::*;
+use ethcontract::transaction::TransactionBuilder;
+use ethcontract::Account;
+use std::fmt;
 use std::str::FromStr;
 
 ethcontract::contract!("artifacts/contracts/RepositoryContract.sol/RepositoryContract.json");
 
+// the hashes sent in one call, so a large repository doesn't hit the gas
+// limit of `eth_call` or the size limit of the RPC node
+const HASHES_PER_CALL: usize = 500;
+
 pub struct ContractInteraction {
     pub contract: RepositoryContract,
     pub client: Web3<Http>,
+    transactions: TransactionManager,
 }
 
 pub struct Object {
@@ -23,90 +35,374 @@ pub struct Ref {
     pub pusher: Address,
 }
 
-impl Default for ContractInteraction {
-    fn default() -> Self {
-        let http =
-            Http::new(&dotenv::var("RPC_URL").unwrap_or("http://localhost:8545".to_string()))
-                .unwrap();
-        let client = Web3::new(http);
+/// An update or deletion of a ref, from the history the contract keeps.
+pub struct RefLogEntry {
+    /// The index of the entry in the history.
+    pub id: u64,
+    pub reference: Ref,
+    pub block_number: u64,
+    /// The timestamp of the block, in seconds since the epoch.
+    pub timestamp: u64,
+}
+
+/// The rules of a protected ref, beyond write access.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Protection {
+    /// The only addresses that may update the ref, any writer when empty.
+    pub pushers: Vec<Address>,
+    /// Whether updates declared as non-fast-forward are accepted. The
+    /// contract can't tell fast-forwards apart, so this keeps clients from
+    /// force-pushing by mistake but doesn't stop a pusher from rewriting
+    /// the ref: restrict that with `pushers`.
+    pub allow_force: bool,
+    /// Whether the ref may be deleted.
+    pub allow_deletion: bool,
+}
+
+impl Protection {
+    /// Fail like the contract would if `pusher` updated `name`, so the push
+    /// stops before paying for a transaction that reverts.
+    pub fn check_update(&self, name: &str, pusher: Address, forced: bool) -> Result<()> {
+        if !self.pushers.is_empty() && !self.pushers.contains(&pusher) {
+            return Err(Error::AccessDenied(format!(
+                "{} is protected and {} may not push to it",
+                name,
+                address_to_string(&pusher)
+            )));
+        }
+
+        if forced && !self.allow_force {
+            return Err(Error::AccessDenied(format!(
+                "{} is protected against force-updates, pull and push a fast-forward instead",
+                name
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Fail like the contract would if `pusher` deleted `name`.
+    pub fn check_deletion(&self, name: &str, pusher: Address) -> Result<()> {
+        if !self.pushers.is_empty() && !self.pushers.contains(&pusher) {
+            return Err(Error::AccessDenied(format!(
+                "{} is protected and {} may not push to it",
+                name,
+                address_to_string(&pusher)
+            )));
+        }
+
+        if !self.allow_deletion {
+            return Err(Error::AccessDenied(format!(
+                "{} is protected against deletion",
+                name
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// What an address may do on a repository contract, each role including
+/// the rights of the ones below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    None,
+    /// Saves objects and updates refs.
+    Writer,
+    /// Also grants and revokes the writer role and updates the config.
+    Maintainer,
+    /// Also manages maintainers and owners.
+    Owner,
+}
+
+impl Role {
+    // the `Role` enum of the contract, encoded as its index
+    fn from_index(index: u8) -> Role {
+        match index {
+            1 => Role::Writer,
+            2 => Role::Maintainer,
+            3 => Role::Owner,
+            _ => Role::None,
+        }
+    }
+
+    fn index(self) -> u8 {
+        self as u8
+    }
+}
 
-        let account_contract = Config::get_contract_address();
+impl FromStr for Role {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "writer" => Ok(Role::Writer),
+            "maintainer" => Ok(Role::Maintainer),
+            "owner" => Ok(Role::Owner),
+            _ => Err(Error::Config(format!(
+                "Unknown role {}, expected writer, maintainer or owner",
+                s
+            ))),
+        }
+    }
+}
 
-        let contract = RepositoryContract::at(
-            &client,
-            Address::from_str(account_contract.as_str()).unwrap(),
-        );
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Role::None => "none",
+            Role::Writer => "writer",
+            Role::Maintainer => "maintainer",
+            Role::Owner => "owner",
+        };
+
+        write!(f, "{}", name)
+    }
+}
 
-        ContractInteraction { contract, client }
+pub fn address_to_string(address: &Address) -> String {
+    let mut result = "0x".to_string();
+    for byte in address.to_fixed_bytes() {
+        result.push_str(&format!("{:02x}", byte));
+    }
+
+    result
+}
+
+fn client() -> Result<Web3<Http>> {
+    client_with_url(&Config::rpc_url()?)
+}
+
+fn client_with_url(rpc_url: &str) -> Result<Web3<Http>> {
+    let http = Http::new(rpc_url).map_err(Error::chain)?;
+
+    Ok(Web3::new(http))
+}
+
+pub fn parse_address(address: &str) -> Result<Address> {
+    Address::from_str(address).map_err(|_| Error::InvalidAddress(address.to_string()))
+}
+
+async fn node_account(client: &Web3<Http>) -> Result<Address> {
+    let accounts = client.eth().accounts().await.map_err(Error::chain)?;
+
+    accounts
+        .first()
+        .copied()
+        .ok_or_else(|| Error::Chain("The node has no accounts".into()))
+}
+
+/// The account transactions are sent from: the configured key, signing
+/// locally for `dgit.chainId` (or the node's chain id when unset), or
+/// the node's first unlocked account.
+async fn sender(client: &Web3<Http>) -> Result<Account> {
+    match signer_key()? {
+        Some(key) => Ok(Account::Offline(key, Config::chain_id()?)),
+        None => Ok(Account::Local(node_account(client).await?, None)),
     }
 }
 
 impl ContractInteraction {
-    pub fn new() -> Self {
-        Self::default()
+    /// Connect to the contract configured for the current repository.
+    pub fn new() -> Result<Self> {
+        Self::new_with_address(&Config::get_contract_address()?)
     }
 
     pub async fn deploy() -> Result<Self> {
-        let http =
-            Http::new(&dotenv::var("RPC_URL").unwrap_or("http://localhost:8545".to_string()))
-                .unwrap();
-        let client = Web3::new(http);
+        let client = client()?;
 
+        // gas is estimated by the node rather than fixed
         let contract = RepositoryContract::builder(&client)
-            .gas(4_000_000.into())
+            .from(sender(&client).await?)
+            .confirmations(Config::confirmations()?)
             .deploy()
-            .await?;
+            .await
+            .map_err(Error::chain)?;
 
-        Ok(ContractInteraction { contract, client })
+        Ok(ContractInteraction {
+            contract,
+            transactions: TransactionManager::new(client.clone())?,
+            client,
+        })
     }
 
     pub fn address(&self) -> String {
-        let bytes = self.contract.address().to_fixed_bytes();
+        address_to_string(&self.contract.address())
+    }
 
-        let mut address = "0x".to_string();
-        for byte in bytes {
-            address.push_str(&format!("{:02x}", byte));
+    /// The address transactions are sent from.
+    pub async fn signer_address() -> Result<String> {
+        match signer_key()? {
+            Some(key) => Ok(address_to_string(&key.public_address())),
+            None => Ok(address_to_string(&node_account(&client()?).await?)),
         }
+    }
+
+    /// The address transactions to this contract are sent from.
+    pub async fn sender_address(&self) -> Result<Address> {
+        Ok(sender(&self.client).await?.address())
+    }
+
+    /// Connect to the contract of a remote, through its own RPC endpoint if set.
+    pub fn for_remote(remote: &Remote) -> Result<Self> {
+        let client = match &remote.rpc_url {
+            Some(rpc_url) => client_with_url(rpc_url)?,
+            None => client()?,
+        };
+
+        let contract = RepositoryContract::at(&client, parse_address(&remote.contract_address)?);
 
-        address
+        Ok(ContractInteraction {
+            contract,
+            transactions: TransactionManager::new(client.clone())?,
+            client,
+        })
     }
 
-    pub fn new_with_address(address: &str) -> Self {
-        let http =
-            Http::new(&dotenv::var("RPC_URL").unwrap_or("http://localhost:8545".to_string()))
-                .unwrap();
-        let client = Web3::new(http);
+    pub fn new_with_address(address: &str) -> Result<Self> {
+        let client = client()?;
+
+        let contract = RepositoryContract::at(&client, parse_address(address)?);
 
-        let contract = RepositoryContract::at(&client, Address::from_str(address).unwrap());
+        Ok(ContractInteraction {
+            contract,
+            transactions: TransactionManager::new(client.clone())?,
+            client,
+        })
+    }
+
+    // send a contract call from the configured account, through the
+    // transaction manager
+    async fn send(&self, transaction: TransactionBuilder<DynTransport>) -> Result<TransactionCost> {
+        let sender = sender(&self.client).await?;
 
-        ContractInteraction { contract, client }
+        self.transactions
+            .send(sender.address(), transaction.from(sender))
+            .await
     }
 
-    pub async fn save_object(&self, hash: String, ipfs_url: Vec<u8>) -> Result<()> {
-        self.contract
-            .save_object(hash, Bytes(ipfs_url))
-            .send()
+    pub async fn save_object(&self, hash: String, ipfs_url: Vec<u8>) -> Result<TransactionCost> {
+        self.send(self.contract.save_object(hash, Bytes(ipfs_url)).tx)
             .await
-            .map_err(anyhow::Error::from)
-            .map(|_| ())
     }
 
-    pub async fn add_ref(&self, reference: String, data: Vec<u8>) -> Result<()> {
+    /// Set a ref that currently holds `old`, empty for a new ref; the
+    /// contract reverts when it holds anything else.
+    pub async fn add_ref(
+        &self,
+        reference: String,
+        old: Vec<u8>,
+        data: Vec<u8>,
+    ) -> Result<TransactionCost> {
+        self.send(self.contract.add_ref(reference, Bytes(old), Bytes(data)).tx)
+            .await
+    }
+
+    pub async fn update_config(&self, config: Vec<u8>) -> Result<TransactionCost> {
+        self.send(self.contract.update_config(Bytes(config)).tx)
+            .await
+    }
+
+    pub async fn get_role(&self, account: Address) -> Result<Role> {
         self.contract
-            .add_ref(reference, Bytes(data))
-            .send()
+            .get_role(account)
+            .call()
+            .await
+            .map(Role::from_index)
+            .map_err(Error::chain)
+    }
+
+    /// The addresses that have a role, in the order they were first granted one.
+    pub async fn get_members(&self) -> Result<Vec<(Address, Role)>> {
+        let (accounts, roles) = self
+            .contract
+            .get_members()
+            .call()
+            .await
+            .map_err(Error::chain)?;
+
+        Ok(accounts
+            .into_iter()
+            .zip(roles.into_iter().map(Role::from_index))
+            .collect())
+    }
+
+    pub async fn grant_role(&self, account: Address, role: Role) -> Result<TransactionCost> {
+        self.send(self.contract.grant_role(account, role.index()).tx)
+            .await
+    }
+
+    pub async fn revoke_role(&self, account: Address) -> Result<TransactionCost> {
+        self.send(self.contract.revoke_role(account).tx).await
+    }
+
+    /// Fail unless the sending account has at least `role`, before sending a
+    /// transaction the contract would revert.
+    pub async fn require_role(&self, role: Role) -> Result<()> {
+        let account = self.sender_address().await?;
+        let current = self.get_role(account).await?;
+
+        if current >= role {
+            return Ok(());
+        }
+
+        let account = address_to_string(&account);
+
+        Err(Error::AccessDenied(format!(
+            "{} needs the {} role on {} but has {}, ask a maintainer to run `dgit access grant {} {}`",
+            account,
+            role,
+            self.address(),
+            current,
+            account,
+            role
+        )))
+    }
+
+    /// The rules of `name`, `None` when it isn't protected.
+    pub async fn get_protection(&self, name: String) -> Result<Option<Protection>> {
+        let (is_protected, pushers, allow_force, allow_deletion) = self
+            .contract
+            .get_protection(name)
+            .call()
             .await
-            .map_err(anyhow::Error::from)
-            .map(|_| ())
+            .map_err(Error::chain)?;
+
+        Ok(is_protected.then_some(Protection {
+            pushers,
+            allow_force,
+            allow_deletion,
+        }))
     }
 
-    pub async fn update_config(&self, config: Vec<u8>) -> Result<()> {
+    pub async fn get_protected_refs(&self) -> Result<Vec<String>> {
         self.contract
-            .update_config(Bytes(config))
-            .send()
+            .get_protected_refs()
+            .call()
             .await
-            .map_err(anyhow::Error::from)
-            .map(|_| ())
+            .map_err(Error::chain)
+    }
+
+    pub async fn protect_ref(
+        &self,
+        name: String,
+        protection: &Protection,
+    ) -> Result<TransactionCost> {
+        self.send(
+            self.contract
+                .protect_ref(
+                    name,
+                    protection.pushers.clone(),
+                    protection.allow_force,
+                    protection.allow_deletion,
+                )
+                .tx,
+        )
+        .await
+    }
+
+    pub async fn unprotect_ref(&self, name: String) -> Result<TransactionCost> {
+        self.send(self.contract.unprotect_ref(name).tx).await
     }
 
     pub async fn get_config(&self) -> Result<Vec<u8>> {
@@ -115,7 +411,7 @@ impl ContractInteraction {
             .call()
             .await
             .map(|Bytes(data)| data.to_vec())
-            .map_err(anyhow::Error::from)
+            .map_err(Error::chain)
     }
 
     pub async fn get_object_by_id(&self, id: U256) -> Result<Object> {
@@ -124,7 +420,7 @@ impl ContractInteraction {
             .get_object_by_id(id)
             .call()
             .await
-            .map_err(anyhow::Error::from)?;
+            .map_err(Error::chain)?;
 
         Ok(Object {
             hash,
@@ -139,7 +435,7 @@ impl ContractInteraction {
             .get_object(hash)
             .call()
             .await
-            .map_err(anyhow::Error::from)?;
+            .map_err(Error::chain)?;
 
         Ok(Object {
             hash,
@@ -153,48 +449,206 @@ impl ContractInteraction {
             .is_object_exist(hash)
             .call()
             .await
-            .map_err(anyhow::Error::from)
+            .map_err(Error::chain)
     }
 
+    /// Whether each of `hashes` is saved on the contract.
     pub async fn check_objects(&self, hashes: Vec<String>) -> Result<Vec<bool>> {
+        let mut result = Vec::new();
+
+        for batch in hashes.chunks(HASHES_PER_CALL) {
+            result.extend(
+                self.contract
+                    .check_objects(batch.to_vec())
+                    .call()
+                    .await
+                    .map_err(Error::chain)?,
+            );
+        }
+
+        Ok(result)
+    }
+
+    /// The objects saved under `hashes`, with an empty `ipfs_url` for the
+    /// ones the contract doesn't know.
+    pub async fn get_objects_by_hash(&self, hashes: Vec<String>) -> Result<Vec<Object>> {
+        let mut result = Vec::new();
+
+        for batch in hashes.chunks(HASHES_PER_CALL) {
+            let objects = self
+                .contract
+                .get_objects_by_hash(batch.to_vec())
+                .call()
+                .await
+                .map_err(Error::chain)?;
+
+            result.extend(objects.into_iter().map(|object| Object {
+                hash: object.0,
+                ipfs_url: object.1 .0,
+                pusher: object.2,
+            }));
+        }
+
+        Ok(result)
+    }
+
+    pub async fn add_objects(
+        &self,
+        hashes: Vec<String>,
+        ipfs_urls: Vec<Vec<u8>>,
+    ) -> Result<TransactionCost> {
+        self.send(
+            self.contract
+                .add_objects(
+                    hashes,
+                    ipfs_urls
+                        .iter()
+                        .map(|e| Bytes(e.clone()))
+                        .collect::<Vec<Bytes<Vec<u8>>>>(),
+                )
+                .tx,
+        )
+        .await
+    }
+
+    /// Set refs that currently hold `old`, like `add_ref`.
+    pub async fn add_refs(
+        &self,
+        references: Vec<String>,
+        old: Vec<Vec<u8>>,
+        data: Vec<Vec<u8>>,
+    ) -> Result<TransactionCost> {
+        self.send(
+            self.contract
+                .add_refs(
+                    references,
+                    old.into_iter().map(Bytes).collect(),
+                    data.iter()
+                        .map(|e| Bytes(e.clone()))
+                        .collect::<Vec<Bytes<Vec<u8>>>>(),
+                )
+                .tx,
+        )
+        .await
+    }
+
+    /// Like `add_refs`, for updates that aren't fast-forwards, which
+    /// protected refs may reject.
+    pub async fn force_add_refs(
+        &self,
+        references: Vec<String>,
+        old: Vec<Vec<u8>>,
+        data: Vec<Vec<u8>>,
+    ) -> Result<TransactionCost> {
+        self.send(
+            self.contract
+                .force_add_refs(
+                    references,
+                    old.into_iter().map(Bytes).collect(),
+                    data.into_iter().map(Bytes).collect(),
+                )
+                .tx,
+        )
+        .await
+    }
+
+    /// Mark refs inactive, the contract keeps the deletion in their history.
+    /// `old` holds the value each ref is expected to have, the deletion
+    /// reverts if one was updated since.
+    pub async fn delete_refs(
+        &self,
+        references: Vec<String>,
+        old: Vec<Vec<u8>>,
+    ) -> Result<TransactionCost> {
+        self.send(
+            self.contract
+                .delete_refs(references, old.into_iter().map(Bytes).collect())
+                .tx,
+        )
+        .await
+    }
+
+    /// The gas `add_objects` would use, without sending it.
+    pub async fn estimate_add_objects(
+        &self,
+        hashes: Vec<String>,
+        ipfs_urls: Vec<Vec<u8>>,
+    ) -> Result<U256> {
         self.contract
-            .check_objects(hashes)
-            .call()
+            .add_objects(hashes, ipfs_urls.into_iter().map(Bytes).collect())
+            .from(sender(&self.client).await?)
+            .tx
+            .estimate_gas()
             .await
-            .map_err(anyhow::Error::from)
+            .map_err(Error::chain)
     }
 
-    pub async fn add_objects(&self, hashes: Vec<String>, ipfs_urls: Vec<Vec<u8>>) -> Result<()> {
+    /// The gas `add_refs` would use, without sending it.
+    pub async fn estimate_add_refs(
+        &self,
+        references: Vec<String>,
+        old: Vec<Vec<u8>>,
+        data: Vec<Vec<u8>>,
+    ) -> Result<U256> {
         self.contract
-            .add_objects(
-                hashes,
-                ipfs_urls
-                    .iter()
-                    .map(|e| Bytes(e.clone()))
-                    .collect::<Vec<Bytes<Vec<u8>>>>(),
+            .add_refs(
+                references,
+                old.into_iter().map(Bytes).collect(),
+                data.into_iter().map(Bytes).collect(),
             )
-            .send()
+            .from(sender(&self.client).await?)
+            .tx
+            .estimate_gas()
             .await
-            .map_err(anyhow::Error::from)
-            .map(|_| ())
+            .map_err(Error::chain)
     }
 
-    pub async fn add_refs(&self, references: Vec<String>, data: Vec<Vec<u8>>) -> Result<()> {
+    /// The gas `force_add_refs` would use, without sending it.
+    pub async fn estimate_force_add_refs(
+        &self,
+        references: Vec<String>,
+        old: Vec<Vec<u8>>,
+        data: Vec<Vec<u8>>,
+    ) -> Result<U256> {
         self.contract
-            .add_refs(
+            .force_add_refs(
                 references,
-                data.iter()
-                    .map(|e| Bytes(e.clone()))
-                    .collect::<Vec<Bytes<Vec<u8>>>>(),
+                old.into_iter().map(Bytes).collect(),
+                data.into_iter().map(Bytes).collect(),
             )
-            .send()
+            .from(sender(&self.client).await?)
+            .tx
+            .estimate_gas()
             .await
-            .map_err(anyhow::Error::from)
-            .map(|_| ())
+            .map_err(Error::chain)
+    }
+
+    /// The gas `delete_refs` would use, without sending it.
+    pub async fn estimate_delete_refs(
+        &self,
+        references: Vec<String>,
+        old: Vec<Vec<u8>>,
+    ) -> Result<U256> {
+        self.contract
+            .delete_refs(references, old.into_iter().map(Bytes).collect())
+            .from(sender(&self.client).await?)
+            .tx
+            .estimate_gas()
+            .await
+            .map_err(Error::chain)
+    }
+
+    pub async fn gas_price(&self) -> Result<U256> {
+        self.client.eth().gas_price().await.map_err(Error::chain)
     }
 
     pub async fn get_objects(&self) -> Result<Vec<Object>> {
-        let objects = self.contract.get_objects().call().await?;
+        let objects = self
+            .contract
+            .get_objects()
+            .call()
+            .await
+            .map_err(Error::chain)?;
         let mut result = Vec::new();
         for object in objects {
             result.push(Object {
@@ -206,8 +660,83 @@ impl ContractInteraction {
         Ok(result)
     }
 
+    /// The current value of every ref that wasn't deleted, one per name.
+    pub async fn get_active_refs(&self) -> Result<Vec<Ref>> {
+        let refs = self
+            .contract
+            .get_active_refs()
+            .call()
+            .await
+            .map_err(Error::chain)?;
+
+        Ok(refs
+            .into_iter()
+            .map(|(name, data, is_active, pusher)| Ref {
+                name,
+                data: data.0,
+                is_active,
+                pusher,
+            })
+            .collect())
+    }
+
+    /// The current value of `name`, `None` when it doesn't exist or was
+    /// deleted.
+    pub async fn get_ref(&self, name: String) -> Result<Option<Ref>> {
+        let (name, data, is_active, pusher) = self
+            .contract
+            .get_ref(name)
+            .call()
+            .await
+            .map_err(Error::chain)?;
+
+        Ok(is_active.then_some(Ref {
+            name,
+            data: data.0,
+            is_active,
+            pusher,
+        }))
+    }
+
+    /// Up to `count` entries of the ref history from `start`, oldest first.
+    pub async fn get_ref_log(&self, start: u64, count: u64) -> Result<Vec<RefLogEntry>> {
+        let (refs, blocks, timestamps) = self
+            .contract
+            .get_ref_log(U256::from(start), U256::from(count))
+            .call()
+            .await
+            .map_err(Error::chain)?;
+
+        Ok(refs
+            .into_iter()
+            .zip(blocks.into_iter().zip(timestamps))
+            .enumerate()
+            .map(
+                |(index, ((name, data, is_active, pusher), (block_number, timestamp)))| {
+                    RefLogEntry {
+                        id: start + index as u64,
+                        reference: Ref {
+                            name,
+                            data: data.0,
+                            is_active,
+                            pusher,
+                        },
+                        block_number: block_number.as_u64(),
+                        timestamp: timestamp.as_u64(),
+                    }
+                },
+            )
+            .collect())
+    }
+
+    /// Every ref update and deletion ever made, oldest first.
     pub async fn get_refs(&self) -> Result<Vec<Ref>> {
-        let objects = self.contract.get_refs().call().await?;
+        let objects = self
+            .contract
+            .get_refs()
+            .call()
+            .await
+            .map_err(Error::chain)?;
         let mut result = Vec::new();
 
         for object in objects {
@@ -226,7 +755,7 @@ impl ContractInteraction {
             .get_objects_length()
             .call()
             .await
-            .map_err(anyhow::Error::from)
+            .map_err(Error::chain)
     }
 
     pub async fn get_refs_length(&self) -> Result<U256> {
@@ -234,7 +763,7 @@ impl ContractInteraction {
             .get_refs_length()
             .call()
             .await
-            .map_err(anyhow::Error::from)
+            .map_err(Error::chain)
     }
 
     pub async fn get_ref_by_id(&self, id: U256) -> Result<Ref> {
@@ -243,7 +772,7 @@ impl ContractInteraction {
             .get_ref_by_id(id)
             .call()
             .await
-            .map_err(anyhow::Error::from)?;
+            .map_err(Error::chain)?;
 
         Ok(Ref {
             name,
@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+type Source = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors of the library that callers may want to tell apart. Commands
+/// still return `anyhow::Error`, with these somewhere in the chain.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Not a dgit repository (or any of the parent directories): {}", .0.display())]
+    NotARepository(PathBuf),
+
+    #[error("Object not found: {0}")]
+    ObjectNotFound(String),
+
+    #[error("Invalid object name: {0}")]
+    InvalidObjectName(String),
+
+    #[error("Corrupt object {hash}: {reason}")]
+    CorruptObject { hash: String, reason: String },
+
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    /// The RPC endpoint or the contract call failed.
+    #[error("Chain request failed: {0}")]
+    Chain(#[source] Source),
+
+    /// The signing account lacks the role the contract requires.
+    #[error("{0}")]
+    AccessDenied(String),
+
+    /// Pinning or downloading object contents failed.
+    #[error("Storage request failed: {0}")]
+    Storage(String),
+
+    /// A setting is missing or has an invalid value.
+    #[error("{0}")]
+    Config(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// A command failed without a message to print, like `cat-file -e` for
+    /// a missing object: only the exit code reports it.
+    #[error("exit status {0}")]
+    Exit(i32),
+}
+
+impl Error {
+    pub fn chain(error: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Error::Chain(Box::new(error))
+    }
+
+    /// The process exit code for this error, following `sysexits.h` where
+    /// one fits and git for a missing repository.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::NotARepository(_) => 128,
+            Error::ObjectNotFound(_) | Error::CorruptObject { .. } => 65,
+            Error::InvalidObjectName(_) | Error::InvalidAddress(_) => 64,
+            Error::Chain(_) => 69,
+            Error::AccessDenied(_) => 77,
+            Error::Storage(_) => 75,
+            Error::Config(_) => 78,
+            Error::Io(_) => 74,
+            Error::Exit(code) => *code,
+        }
+    }
+}
+
+/// The exit code of the first typed error in the chain of `error`, or 1.
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<Error>())
+        .map(Error::exit_code)
+        .unwrap_or(1)
+}
+
+/// Whether `error` only carries an exit code and nothing is printed for it.
+pub fn is_silent(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<Error>(), Some(Error::Exit(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        let error = anyhow::Error::from(Error::ObjectNotFound("abc".to_string()));
+        assert_eq!(exit_code(&error), 65);
+
+        let error = error.context("Failed to read HEAD");
+        assert_eq!(exit_code(&error), 65);
+
+        assert_eq!(exit_code(&anyhow::anyhow!("Something else")), 1);
+
+        let error = anyhow::Error::from(Error::Exit(2));
+        assert_eq!(exit_code(&error), 2);
+        assert!(is_silent(&error));
+        assert!(!is_silent(&anyhow::anyhow!("Something else")));
+    }
+}
@@ -1,27 +1,670 @@
-#[derive(Debug)]
+use crate::git::repository::Repository;
+use anyhow::{anyhow, bail, Error, Result};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The config files, from lowest to highest priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigScope {
+    System,
+    Global,
+    Local,
+}
+
+impl Display for ConfigScope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigScope::System => write!(f, "system"),
+            ConfigScope::Global => write!(f, "global"),
+            ConfigScope::Local => write!(f, "local"),
+        }
+    }
+}
+
+impl ConfigScope {
+    pub fn path(&self) -> Option<PathBuf> {
+        match self {
+            ConfigScope::System => Some(
+                std::env::var("DGIT_CONFIG_SYSTEM")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| PathBuf::from("/etc/gitconfig")),
+            ),
+            ConfigScope::Global => std::env::var("DGIT_CONFIG_GLOBAL")
+                .map(PathBuf::from)
+                .or_else(|_| {
+                    std::env::var("HOME")
+                        .or_else(|_| std::env::var("USERPROFILE"))
+                        .map(|home| Path::new(&home).join(".gitconfig"))
+                })
+                .ok(),
+            ConfigScope::Local => Repository::current()
+                .ok()
+                .map(|repository| repository.config_path()),
+        }
+    }
+}
+
+/// A config key, `section[.subsection].name`. Section and name are case
+/// insensitive, the subsection is not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigKey {
+    pub section: String,
+    pub subsection: Option<String>,
+    pub name: String,
+}
+
+impl ConfigKey {
+    fn in_section(&self, section: &str, subsection: Option<&str>) -> bool {
+        self.section == section && self.subsection.as_deref() == subsection
+    }
+}
+
+impl FromStr for ConfigKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (section, rest) = s
+            .split_once('.')
+            .ok_or_else(|| anyhow!("Key does not contain a section: {}", s))?;
+
+        let (subsection, name) = match rest.rsplit_once('.') {
+            Some((subsection, name)) => (Some(subsection.to_string()), name),
+            None => (None, rest),
+        };
+
+        if section.is_empty() || name.is_empty() {
+            bail!("Invalid key: {}", s);
+        }
+
+        Ok(ConfigKey {
+            section: section.to_lowercase(),
+            subsection,
+            name: name.to_lowercase(),
+        })
+    }
+}
+
+impl Display for ConfigKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.subsection {
+            Some(subsection) => write!(f, "{}.{}.{}", self.section, subsection, self.name),
+            None => write!(f, "{}.{}", self.section, self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ConfigLine {
+    Section {
+        section: String,
+        subsection: Option<String>,
+    },
+    Entry {
+        key: ConfigKey,
+        value: String,
+    },
+    Other,
+}
+
+/// A single config file in git's INI format. Comments and formatting of
+/// untouched lines are preserved when the file is written back.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigFile {
+    path: PathBuf,
+    lines: Vec<(String, ConfigLine)>,
+}
+
+impl ConfigFile {
+    pub fn new(path: &Path) -> Self {
+        ConfigFile {
+            path: path.to_path_buf(),
+            lines: vec![],
+        }
+    }
+
+    /// Load the file, or start an empty one if it does not exist yet.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = ConfigFile::new(path);
+
+        if path.is_file() {
+            file.parse(&std::fs::read_to_string(path)?)?;
+        }
+
+        Ok(file)
+    }
+
+    pub fn parse(&mut self, content: &str) -> Result<()> {
+        let mut section = String::new();
+        let mut subsection = None;
+
+        for (number, raw) in content.lines().enumerate() {
+            let line = raw.trim();
+
+            let parsed = if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                ConfigLine::Other
+            } else if line.starts_with('[') {
+                let (name, sub) = parse_section_header(line)
+                    .ok_or_else(|| anyhow!("Bad config line {} in {:?}", number + 1, self.path))?;
+
+                section = name;
+                subsection = sub;
+
+                ConfigLine::Section {
+                    section: section.clone(),
+                    subsection: subsection.clone(),
+                }
+            } else {
+                if section.is_empty() {
+                    bail!("Bad config line {} in {:?}", number + 1, self.path);
+                }
+
+                let (name, value) = match line.split_once('=') {
+                    Some((name, value)) => (name.trim(), parse_value(value)),
+                    None => (line, "true".to_string()),
+                };
+
+                ConfigLine::Entry {
+                    key: ConfigKey {
+                        section: section.clone(),
+                        subsection: subsection.clone(),
+                        name: name.to_lowercase(),
+                    },
+                    value,
+                }
+            };
+
+            self.lines.push((raw.to_string(), parsed));
+        }
+
+        Ok(())
+    }
+
+    pub fn entries(&self) -> Vec<(ConfigKey, String)> {
+        self.lines
+            .iter()
+            .filter_map(|(_, line)| match line {
+                ConfigLine::Entry { key, value } => Some((key.clone(), value.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn get(&self, key: &ConfigKey) -> Option<String> {
+        self.get_all(key).pop()
+    }
+
+    pub fn get_all(&self, key: &ConfigKey) -> Vec<String> {
+        self.entries()
+            .into_iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Replace all values of `key` with a single one.
+    pub fn set(&mut self, key: &ConfigKey, value: &str) {
+        let first = self
+            .lines
+            .iter()
+            .position(|(_, line)| is_entry_of(line, key));
+
+        match first {
+            Some(index) => {
+                self.lines[index] = entry_line(key, value);
+
+                let mut i = self.lines.len();
+                while i > index + 1 {
+                    i -= 1;
+                    if is_entry_of(&self.lines[i].1, key) {
+                        self.lines.remove(i);
+                    }
+                }
+            }
+            None => self.add(key, value),
+        }
+    }
+
+    /// Add a value for `key`, keeping the existing ones.
+    pub fn add(&mut self, key: &ConfigKey, value: &str) {
+        let section_end = self.section_end(&key.section, key.subsection.as_deref());
+
+        match section_end {
+            Some(index) => self.lines.insert(index, entry_line(key, value)),
+            None => {
+                self.lines.push((
+                    format_section_header(&key.section, key.subsection.as_deref()),
+                    ConfigLine::Section {
+                        section: key.section.clone(),
+                        subsection: key.subsection.clone(),
+                    },
+                ));
+                self.lines.push(entry_line(key, value));
+            }
+        }
+    }
+
+    /// Remove all values of `key`, returning whether anything was removed.
+    pub fn unset(&mut self, key: &ConfigKey) -> bool {
+        let len = self.lines.len();
+
+        self.lines.retain(|(_, line)| !is_entry_of(line, key));
+
+        len != self.lines.len()
+    }
+
+    /// Remove a whole section with all its entries.
+    pub fn remove_section(&mut self, section: &str, subsection: Option<&str>) -> bool {
+        let len = self.lines.len();
+        let mut in_section = false;
+
+        self.lines.retain(|(_, line)| {
+            if let ConfigLine::Section {
+                section: s,
+                subsection: sub,
+            } = line
+            {
+                in_section = s == section && sub.as_deref() == subsection;
+            }
+
+            !in_section
+        });
+
+        len != self.lines.len()
+    }
+
+    /// Rename a section, keeping its entries.
+    pub fn rename_section(
+        &mut self,
+        section: &str,
+        subsection: Option<&str>,
+        new_subsection: Option<&str>,
+    ) -> bool {
+        let mut renamed = false;
+
+        for (raw, line) in self.lines.iter_mut() {
+            match line {
+                ConfigLine::Section {
+                    section: s,
+                    subsection: sub,
+                } if s == section && sub.as_deref() == subsection => {
+                    *sub = new_subsection.map(|x| x.to_string());
+                    *raw = format_section_header(section, new_subsection);
+                    renamed = true;
+                }
+                ConfigLine::Entry { key, .. } if key.in_section(section, subsection) => {
+                    key.subsection = new_subsection.map(|x| x.to_string());
+                }
+                _ => {}
+            }
+        }
+
+        renamed
+    }
+
+    /// The subsections of every `[section "..."]` in the file.
+    pub fn subsections(&self, section: &str) -> Vec<String> {
+        let mut result = Vec::new();
+
+        for (_, line) in &self.lines {
+            if let ConfigLine::Section {
+                section: s,
+                subsection: Some(sub),
+            } = line
+            {
+                if s == section && !result.contains(sub) {
+                    result.push(sub.clone());
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+
+        std::fs::write(&self.path, self.to_string())?;
+
+        Ok(())
+    }
+
+    // index right after the last line of the last matching section
+    fn section_end(&self, section: &str, subsection: Option<&str>) -> Option<usize> {
+        let mut end = None;
+        let mut in_section = false;
+
+        for (i, (_, line)) in self.lines.iter().enumerate() {
+            match line {
+                ConfigLine::Section {
+                    section: s,
+                    subsection: sub,
+                } => {
+                    in_section = s == section && sub.as_deref() == subsection;
+                    if in_section {
+                        end = Some(i + 1);
+                    }
+                }
+                ConfigLine::Entry { .. } if in_section => end = Some(i + 1),
+                _ => {}
+            }
+        }
+
+        end
+    }
+}
+
+impl Display for ConfigFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (raw, _) in &self.lines {
+            writeln!(f, "{}", raw)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_entry_of(line: &ConfigLine, key: &ConfigKey) -> bool {
+    matches!(line, ConfigLine::Entry { key: k, .. } if k == key)
+}
+
+fn entry_line(key: &ConfigKey, value: &str) -> (String, ConfigLine) {
+    (
+        format!("\t{} = {}", key.name, format_value(value)),
+        ConfigLine::Entry {
+            key: key.clone(),
+            value: value.to_string(),
+        },
+    )
+}
+
+fn format_section_header(section: &str, subsection: Option<&str>) -> String {
+    match subsection {
+        Some(subsection) => format!(
+            "[{} \"{}\"]",
+            section,
+            subsection.replace('\\', "\\\\").replace('"', "\\\"")
+        ),
+        None => format!("[{}]", section),
+    }
+}
+
+// [section], [section "subsection"] or the legacy [section.subsection]
+fn parse_section_header(line: &str) -> Option<(String, Option<String>)> {
+    let inner = &line[1..line.find(']')?];
+
+    if let Some((section, rest)) = inner.split_once(char::is_whitespace) {
+        let rest = rest.trim();
+        let subsection = rest.strip_prefix('"')?.strip_suffix('"')?;
+
+        return Some((
+            section.to_lowercase(),
+            Some(subsection.replace("\\\"", "\"").replace("\\\\", "\\")),
+        ));
+    }
+
+    match inner.split_once('.') {
+        Some((section, subsection)) => {
+            Some((section.to_lowercase(), Some(subsection.to_lowercase())))
+        }
+        None => Some((inner.to_lowercase(), None)),
+    }
+}
+
+// strip comments and quotes and unescape a raw value
+fn parse_value(raw: &str) -> String {
+    let mut value = String::new();
+    let mut in_quotes = false;
+    let mut chars = raw.trim().chars();
+    let mut trailing_space = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '#' | ';' if !in_quotes => break,
+            '\\' => {
+                match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(c) => value.push(c),
+                    None => {}
+                }
+                trailing_space = 0;
+                continue;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                value.push(c);
+                trailing_space += c.len_utf8();
+                continue;
+            }
+            c => value.push(c),
+        }
+
+        trailing_space = 0;
+    }
+
+    value.truncate(value.len() - trailing_space);
+
+    value
+}
+
+fn format_value(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+
+    let needs_quotes = value.starts_with(char::is_whitespace)
+        || value.ends_with(char::is_whitespace)
+        || value.contains(['#', ';']);
+
+    if needs_quotes {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// The system, global and repository config files layered on top of each
+/// other; values from later layers take priority.
+#[derive(Debug, Default)]
 pub struct Config {
-    pub repository_address: String,
+    layers: Vec<(ConfigScope, ConfigFile)>,
 }
 
 impl Config {
-    pub fn new(repository_address: String) -> Self {
-        Config { repository_address }
+    /// The config of the current repository, if any.
+    pub fn load() -> Result<Self> {
+        Config::load_with_local(ConfigScope::Local.path().as_deref())
     }
 
-    pub fn get() -> Self {
-        // load config from file .git/DGITCONFIG
-        let content = std::fs::read("./.git/DGITCONFIG").unwrap();
+    /// The system and global config with the given repository config on top.
+    pub fn load_with_local(local: Option<&Path>) -> Result<Self> {
+        let mut layers = Vec::new();
 
-        let content = String::from_utf8(content).unwrap();
+        let paths = [
+            (ConfigScope::System, ConfigScope::System.path()),
+            (ConfigScope::Global, ConfigScope::Global.path()),
+            (ConfigScope::Local, local.map(Path::to_path_buf)),
+        ];
 
-        let repository_address = content.trim();
+        for (scope, path) in paths {
+            if let Some(path) = path {
+                if path.is_file() {
+                    layers.push((scope, ConfigFile::open(&path)?));
+                }
+            }
+        }
 
-        Config::new(repository_address.to_string())
+        Ok(Config { layers })
     }
 
-    pub async fn save(&self) -> anyhow::Result<()> {
-        tokio::fs::write("./.git/DGITCONFIG", self.repository_address.as_bytes()).await?;
+    pub fn open_scope(scope: ConfigScope) -> Result<ConfigFile> {
+        let path = match scope {
+            ConfigScope::Local => Repository::current()?.config_path(),
+            scope => scope
+                .path()
+                .ok_or_else(|| anyhow!("Unable to locate the {} config file", scope))?,
+        };
 
-        Ok(())
+        ConfigFile::open(&path)
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.get_all(key).pop()
+    }
+
+    pub fn get_all(&self, key: &str) -> Vec<String> {
+        let Ok(key) = ConfigKey::from_str(key) else {
+            return vec![];
+        };
+
+        self.layers
+            .iter()
+            .flat_map(|(_, file)| file.get_all(&key))
+            .collect()
+    }
+
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>> {
+        self.get(key)
+            .map(|value| match value.to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Ok(true),
+                "false" | "no" | "off" | "0" | "" => Ok(false),
+                _ => bail!("Bad boolean config value '{}' for '{}'", value, key),
+            })
+            .transpose()
+    }
+
+    pub fn get_u64(&self, key: &str) -> Result<Option<u64>> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| anyhow!("Bad numeric config value '{}' for '{}'", value, key))
+            })
+            .transpose()
+    }
+
+    pub fn entries(&self) -> Vec<(ConfigScope, ConfigKey, String)> {
+        self.layers
+            .iter()
+            .flat_map(|(scope, file)| {
+                file.entries()
+                    .into_iter()
+                    .map(|(key, value)| (*scope, key, value))
+            })
+            .collect()
+    }
+
+    /// Set a value in the config file of the given scope.
+    pub fn set_value(scope: ConfigScope, key: &str, value: &str) -> Result<()> {
+        let mut file = Config::open_scope(scope)?;
+
+        file.set(&ConfigKey::from_str(key)?, value);
+
+        file.save()
+    }
+
+    /// Remove a value from the config file of the given scope.
+    pub fn unset_value(scope: ConfigScope, key: &str) -> Result<bool> {
+        let mut file = Config::open_scope(scope)?;
+
+        let removed = file.unset(&ConfigKey::from_str(key)?);
+
+        if removed {
+            file.save()?;
+        }
+
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> ConfigFile {
+        let mut file = ConfigFile::new(Path::new("config"));
+        file.parse(content).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_config_key_from_str() {
+        let key = ConfigKey::from_str("remote.Origin.URL").unwrap();
+
+        assert_eq!(key.section, "remote");
+        assert_eq!(key.subsection.as_deref(), Some("Origin"));
+        assert_eq!(key.name, "url");
+        assert_eq!(key.to_string(), "remote.Origin.url");
+        assert!(ConfigKey::from_str("nosection").is_err());
+    }
+
+    #[test]
+    fn test_config_file_parse() {
+        let file = parse(
+            "# comment\n[core]\n\tbare = false\n[dgit]\n\tcontractAddress = 0xabc ; note\n\
+             \tgateway = \"https://a/ipfs/\"\n\tgateway = https://b/ipfs/\n\
+             [remote \"origin\"]\n\turl = dgit::0x1\n",
+        );
+
+        let key = |s: &str| ConfigKey::from_str(s).unwrap();
+
+        assert_eq!(file.get(&key("core.bare")).as_deref(), Some("false"));
+        assert_eq!(
+            file.get(&key("dgit.contractaddress")).as_deref(),
+            Some("0xabc")
+        );
+        assert_eq!(
+            file.get_all(&key("dgit.gateway")),
+            vec!["https://a/ipfs/", "https://b/ipfs/"]
+        );
+        assert_eq!(
+            file.get(&key("remote.origin.url")).as_deref(),
+            Some("dgit::0x1")
+        );
+    }
+
+    #[test]
+    fn test_config_file_set_and_unset() {
+        let mut file = parse("# keep me\n[core]\n\tbare = false\n");
+
+        let key = |s: &str| ConfigKey::from_str(s).unwrap();
+
+        file.set(&key("core.bare"), "true");
+        file.set(&key("dgit.rpcUrl"), "http://localhost:8545");
+        file.add(
+            &key("remote.origin.fetch"),
+            "+refs/heads/*:refs/remotes/origin/*",
+        );
+
+        assert_eq!(
+            file.to_string(),
+            "# keep me\n[core]\n\tbare = true\n[dgit]\n\trpcurl = http://localhost:8545\n\
+             [remote \"origin\"]\n\tfetch = +refs/heads/*:refs/remotes/origin/*\n"
+        );
+
+        assert!(file.unset(&key("core.bare")));
+        assert!(!file.unset(&key("core.bare")));
+        assert!(file.remove_section("remote", Some("origin")));
+        assert_eq!(file.subsections("remote"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_format_value_round_trip() {
+        for value in ["plain", " padded ", "with # hash", "quote \" and \\ slash"] {
+            assert_eq!(parse_value(&format_value(value)), value);
+        }
+    }
+
+    #[test]
+    fn test_parse_value_multibyte_space() {
+        assert_eq!(parse_value("a\u{3000}# c"), "a");
+        assert_eq!(parse_value("a\u{3000}b\u{3000}"), "a\u{3000}b");
     }
 }
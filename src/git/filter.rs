@@ -0,0 +1,98 @@
+use anyhow::{bail, Error, Result};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The `--filter` of a partial clone: which blobs are left out of the
+/// download, to be fetched when something reads them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectFilter {
+    /// `blob:none`, no blobs at all.
+    BlobNone,
+    /// `blob:limit=<size>`, only blobs smaller than `size` bytes.
+    BlobLimit(u64),
+}
+
+impl ObjectFilter {
+    /// Whether a blob of `size` bytes is downloaded up front.
+    pub fn allows(&self, size: u64) -> bool {
+        match self {
+            ObjectFilter::BlobNone => false,
+            ObjectFilter::BlobLimit(limit) => size < *limit,
+        }
+    }
+}
+
+// `<n>`, `<n>k`, `<n>m` or `<n>g`, in powers of 1024 like git
+fn parse_size(s: &str) -> Option<u64> {
+    let (number, unit) = match s.to_lowercase().chars().last()? {
+        'k' => (&s[..s.len() - 1], 1 << 10),
+        'm' => (&s[..s.len() - 1], 1 << 20),
+        'g' => (&s[..s.len() - 1], 1 << 30),
+        _ => (s, 1),
+    };
+
+    number.parse::<u64>().ok()?.checked_mul(unit)
+}
+
+impl FromStr for ObjectFilter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "blob:none" {
+            return Ok(ObjectFilter::BlobNone);
+        }
+
+        if let Some(size) = s.strip_prefix("blob:limit=") {
+            if let Some(size) = parse_size(size) {
+                return Ok(ObjectFilter::BlobLimit(size));
+            }
+        }
+
+        bail!(
+            "Invalid filter '{}', expected blob:none or blob:limit=<size>",
+            s
+        )
+    }
+}
+
+impl Display for ObjectFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjectFilter::BlobNone => write!(f, "blob:none"),
+            ObjectFilter::BlobLimit(size) => write!(f, "blob:limit={}", size),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_filter_from_str() {
+        assert_eq!(
+            ObjectFilter::from_str("blob:none").unwrap(),
+            ObjectFilter::BlobNone
+        );
+        assert_eq!(
+            ObjectFilter::from_str("blob:limit=100").unwrap(),
+            ObjectFilter::BlobLimit(100)
+        );
+        assert_eq!(
+            ObjectFilter::from_str("blob:limit=1m").unwrap(),
+            ObjectFilter::BlobLimit(1 << 20)
+        );
+
+        assert!(ObjectFilter::from_str("blob:limit=").is_err());
+        assert!(ObjectFilter::from_str("blob:limit=1x").is_err());
+        assert!(ObjectFilter::from_str("tree:0").is_err());
+    }
+
+    #[test]
+    fn test_object_filter_allows() {
+        assert!(!ObjectFilter::BlobNone.allows(0));
+        assert!(ObjectFilter::BlobLimit(1024).allows(1023));
+        assert!(!ObjectFilter::BlobLimit(1024).allows(1024));
+        assert_eq!(ObjectFilter::BlobLimit(1024).to_string(), "blob:limit=1024");
+    }
+}
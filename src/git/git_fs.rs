@@ -1,44 +1,59 @@
+use crate::error::{self, Error};
 use crate::git::objects::blob::BlobObject;
+use crate::git::objects::commit::CommitObject;
+use crate::git::objects::header::ObjectHeader;
 use crate::git::objects::tree::TreeObject;
+use crate::git::repository::Repository;
+use crate::promisor;
 use anyhow::Result;
-use flate2::read::ZlibDecoder;
-use std::io::Read;
 
-pub fn get_raw_object(path: &str) -> Result<Vec<u8>> {
-    let path = path.trim();
+// the functions below work on the repository of the current directory, see
+// `Repository` for other repositories
 
-    let (prefix, data) = path.split_at(2);
-
-    let object_path = format!(".git/objects/{}/{}", prefix, data);
-
-    let data = std::fs::read(object_path)?;
-
-    // decompress the data
-    let mut z = ZlibDecoder::new(&data[..]);
-    let mut data = Vec::new();
-    z.read_to_end(&mut data).unwrap();
+pub fn get_raw_object(path: &str) -> error::Result<Vec<u8>> {
+    // objects a partial clone left out are fetched on first use
+    promisor::read_object(&Repository::current()?, path)
+}
 
-    Ok(data)
+// an object that was read but doesn't parse as its type
+fn corrupt(hash: &str, error: anyhow::Error) -> Error {
+    Error::CorruptObject {
+        hash: hash.trim().to_string(),
+        reason: error.to_string(),
+    }
 }
 
 pub fn get_blob_object(path: &str) -> Result<BlobObject> {
     let data = get_raw_object(path)?;
 
-    BlobObject::try_from(data)
+    Ok(BlobObject::try_from(data).map_err(|e| corrupt(path, e))?)
 }
 
 pub fn get_tree_object(path: &str) -> Result<TreeObject> {
     let data = get_raw_object(path)?;
 
-    TreeObject::try_from(data)
+    Ok(TreeObject::try_from(data).map_err(|e| corrupt(path, e))?)
 }
 
-pub fn check_if_object_exists(path: &str) -> bool {
-    let path = path.trim();
+pub fn get_commit_object(path: &str) -> Result<CommitObject> {
+    let data = get_raw_object(path)?;
 
-    let (prefix, data) = path.split_at(2);
+    Ok(CommitObject::try_from(data).map_err(|e| corrupt(path, e))?)
+}
 
-    let object_path = format!(".git/objects/{}/{}", prefix, data);
+pub fn get_object_header(path: &str) -> Result<ObjectHeader> {
+    let data = get_raw_object(path)?;
+
+    Ok(ObjectHeader::try_from(data).map_err(|e| corrupt(path, e))?)
+}
 
-    std::path::Path::new(&object_path).exists()
+// find all objects whose hash starts with the given (abbreviated) prefix
+pub fn find_objects_by_prefix(prefix: &str) -> Result<Vec<String>> {
+    Repository::current()?.find_objects_by_prefix(prefix)
+}
+
+pub fn check_if_object_exists(path: &str) -> bool {
+    Repository::current()
+        .map(|repository| repository.has_object(path))
+        .unwrap_or(false)
 }
@@ -1,33 +1,29 @@
 use crate::git::git_fs::{check_if_object_exists, get_blob_object, get_raw_object};
 use crate::git::objects::header::ObjectType;
-use crate::git::refs::Ref;
+use crate::git::repository::Repository;
 use anyhow::Result;
-use serde::__private::from_utf8_lossy;
 use std::path::Path;
 
 pub async fn get_head() -> Result<String> {
-    let head = tokio::fs::read_to_string(".git/HEAD").await?;
-    let head = head.trim();
-    let head = head.split(' ').collect::<Vec<&str>>()[1];
-    Ok(head.to_string())
+    Repository::current()?.head()
 }
 
-pub async fn update_head(hash: &str) -> Result<()> {
-    let head = get_head().await?;
+/// Move the current branch, or a detached `HEAD` itself, to `hash`,
+/// recording `message` in the reflog.
+pub async fn update_head(hash: &str, message: &str) -> Result<()> {
+    let repository = Repository::current()?;
+    let head = repository.head()?;
 
-    let mut head = Ref::new(&head);
-
-    head.update(hash).await?;
-
-    Ok(())
+    if head.starts_with("refs/") {
+        repository.update_ref_logged(&head, hash, message)
+    } else {
+        // a detached HEAD is moved itself
+        repository.update_ref_logged("HEAD", hash, message)
+    }
 }
 
 pub async fn resolve_head() -> Result<String> {
-    let head = get_head().await?;
-
-    let ref_ = Ref::new(&Ref::parse_ref_content(&head));
-
-    ref_.resolve().await
+    Repository::current()?.resolve_ref("HEAD")
 }
 
 pub async fn update_current_files_to_current_head() -> Result<()> {
@@ -47,7 +43,7 @@ pub async fn update_current_files_to_current_head() -> Result<()> {
 
         let tree = body.split(|c| *c == b' ').collect::<Vec<&[u8]>>()[1];
 
-        from_utf8_lossy(tree).to_string()
+        String::from_utf8_lossy(tree).to_string()
     } else {
         head
     };
@@ -61,7 +57,11 @@ pub async fn update_current_files_to_current_head() -> Result<()> {
 
     let tree_obj = crate::git::objects::tree::TreeObject::try_from(raw_object)?;
 
-    let files = tree_obj.get_files_recursive(".").await?;
+    let work_tree = Repository::current()?.work_tree()?.to_path_buf();
+
+    let files = tree_obj
+        .get_files_recursive(&work_tree.to_string_lossy())
+        .await?;
 
     // save files to disk
     for (path, data) in files {
@@ -0,0 +1,232 @@
+use crate::git::config::Config;
+use crate::git::objects::commit::CommitAuthor;
+use anyhow::{anyhow, bail, Error, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdentityRole {
+    Author,
+    Committer,
+}
+
+impl IdentityRole {
+    fn env_prefix(&self) -> &'static str {
+        match self {
+            IdentityRole::Author => "DGIT_AUTHOR",
+            IdentityRole::Committer => "DGIT_COMMITTER",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+impl Identity {
+    pub fn signature(&self, (timestamp, timezone): (i64, String)) -> CommitAuthor {
+        CommitAuthor {
+            name: self.name.clone(),
+            email: self.email.clone(),
+            timestamp,
+            timezone,
+        }
+    }
+}
+
+impl FromStr for Identity {
+    type Err = Error;
+
+    // Name <email>
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, rest) = s
+            .split_once('<')
+            .ok_or_else(|| anyhow!("Identity must be in the form 'Name <email>': {}", s))?;
+
+        let email = rest
+            .strip_suffix('>')
+            .ok_or_else(|| anyhow!("Identity must be in the form 'Name <email>': {}", s))?;
+
+        Ok(Identity {
+            name: name.trim().to_string(),
+            email: email.trim().to_string(),
+        })
+    }
+}
+
+/// Look up a value in the layered config.
+pub fn config_value(key: &str) -> Option<String> {
+    Config::load().ok().and_then(|config| config.get(key))
+}
+
+// like git, fall back to `$USER@$HOSTNAME` when nothing is configured
+fn default_identity() -> Identity {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "dgit".to_string());
+
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string());
+
+    Identity {
+        email: format!("{}@{}", user, host),
+        name: user,
+    }
+}
+
+// the identity configured for `role` in `DGIT_<ROLE>_NAME` /
+// `DGIT_<ROLE>_EMAIL` or `user.name` / `user.email`, the default one for
+// the parts that aren't, and whether both parts were
+fn lookup_identity(role: IdentityRole) -> (Identity, bool) {
+    let prefix = role.env_prefix();
+
+    let name = std::env::var(format!("{}_NAME", prefix))
+        .ok()
+        .or_else(|| config_value("user.name"));
+
+    let email = std::env::var(format!("{}_EMAIL", prefix))
+        .ok()
+        .or_else(|| config_value("user.email"));
+
+    let configured = name.is_some() && email.is_some();
+    let fallback = default_identity();
+
+    let identity = Identity {
+        name: name.unwrap_or(fallback.name),
+        email: email.unwrap_or(fallback.email),
+    };
+
+    (identity, configured)
+}
+
+/// The committer identity for records git writes regardless of it being
+/// configured, like the reflog, without warning.
+pub fn reflog_identity() -> Identity {
+    lookup_identity(IdentityRole::Committer).0
+}
+
+/// Resolve the identity for the given role from `DGIT_<ROLE>_NAME` /
+/// `DGIT_<ROLE>_EMAIL`, then `user.name` / `user.email` in the config.
+pub fn resolve_identity(role: IdentityRole) -> Result<Identity> {
+    let prefix = role.env_prefix();
+    let (identity, configured) = lookup_identity(role);
+
+    if !configured {
+        eprintln!(
+            "warning: identity not configured, set user.name and user.email in \
+             .git/config or ~/.gitconfig, or {0}_NAME and {0}_EMAIL",
+            prefix
+        );
+    }
+
+    if identity.name.is_empty() || identity.email.is_empty() {
+        bail!("Empty identity name or email is not allowed");
+    }
+
+    Ok(identity)
+}
+
+pub fn format_timezone(offset_seconds: i32) -> String {
+    let sign = if offset_seconds < 0 { '-' } else { '+' };
+    let minutes = offset_seconds.abs() / 60;
+
+    format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+}
+
+/// The current time and local timezone offset.
+pub fn now() -> (i64, String) {
+    let now = Local::now();
+
+    (
+        now.timestamp(),
+        format_timezone(now.offset().local_minus_utc()),
+    )
+}
+
+/// Parse a date given as `@<timestamp>`, `<timestamp> <timezone>`,
+/// RFC 2822, RFC 3339 or `YYYY-MM-DD HH:MM:SS` (local time).
+pub fn parse_date(date: &str) -> Result<(i64, String)> {
+    let date = date.trim();
+
+    if let Some(timestamp) = date.strip_prefix('@') {
+        return Ok((timestamp.parse()?, "+0000".to_string()));
+    }
+
+    if let Some((timestamp, timezone)) = date.split_once(' ') {
+        if let Ok(timestamp) = timestamp.parse::<i64>() {
+            return Ok((timestamp, timezone.trim().to_string()));
+        }
+    }
+
+    let parsed = DateTime::parse_from_rfc2822(date).or_else(|_| DateTime::parse_from_rfc3339(date));
+
+    if let Ok(parsed) = parsed {
+        return Ok((
+            parsed.timestamp(),
+            format_timezone(parsed.offset().local_minus_utc()),
+        ));
+    }
+
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(date, format) {
+            let local = Local
+                .from_local_datetime(&naive)
+                .single()
+                .ok_or_else(|| anyhow!("Ambiguous local date: {}", date))?;
+
+            return Ok((
+                local.timestamp(),
+                format_timezone(local.offset().local_minus_utc()),
+            ));
+        }
+    }
+
+    bail!("Invalid date format: {}", date)
+}
+
+/// The date for the given role, from `DGIT_<ROLE>_DATE` or the current time.
+pub fn resolve_date(role: IdentityRole) -> Result<(i64, String)> {
+    match std::env::var(format!("{}_DATE", role.env_prefix())) {
+        Ok(date) => parse_date(&date),
+        Err(_) => Ok(now()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_from_str() {
+        let identity = Identity::from_str("John Doe <john@example.com>").unwrap();
+
+        assert_eq!(identity.name, "John Doe");
+        assert_eq!(identity.email, "john@example.com");
+        assert!(Identity::from_str("John Doe").is_err());
+    }
+
+    #[test]
+    fn test_format_timezone() {
+        assert_eq!(format_timezone(0), "+0000");
+        assert_eq!(format_timezone(7200), "+0200");
+        assert_eq!(format_timezone(-16200), "-0430");
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("1622519072 +0200").unwrap(),
+            (1622519072, "+0200".to_string())
+        );
+        assert_eq!(
+            parse_date("@1622519072").unwrap(),
+            (1622519072, "+0000".to_string())
+        );
+        assert_eq!(
+            parse_date("2021-06-01T05:44:32+02:00").unwrap(),
+            (1622519072, "+0200".to_string())
+        );
+        assert!(parse_date("yesterday-ish").is_err());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::git::repository::Repository;
+use anyhow::Result;
+
+#[derive(Debug, Clone)]
+struct IgnorePattern {
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+/// Patterns loaded from `.gitignore` and `.git/info/exclude`.
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreRules {
+    pub fn load(repository: &Repository) -> Result<Self> {
+        let mut rules = IgnoreRules::default();
+
+        let files = [
+            repository.git_dir().join("info/exclude"),
+            repository.work_tree()?.join(".gitignore"),
+        ];
+
+        for path in files {
+            if path.is_file() {
+                rules.add_patterns(&std::fs::read_to_string(path)?);
+            }
+        }
+
+        Ok(rules)
+    }
+
+    pub fn add_patterns(&mut self, content: &str) {
+        for line in content.lines() {
+            let line = line.trim_end();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+
+            let anchored = line.contains('/');
+
+            self.patterns.push(IgnorePattern {
+                pattern: line.trim_start_matches('/').to_string(),
+                negated,
+                dir_only,
+                anchored,
+            });
+        }
+    }
+
+    /// Check whether a path relative to the work tree is ignored, either
+    /// directly or because one of its parent directories is.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let components = path.split('/').collect::<Vec<&str>>();
+
+        for i in 0..components.len() {
+            let sub_path = components[..=i].join("/");
+            let sub_is_dir = i + 1 < components.len() || is_dir;
+
+            if self.matches(&sub_path, sub_is_dir) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    // the last matching pattern wins
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+
+        let mut ignored = false;
+
+        for pattern in &self.patterns {
+            if pattern.dir_only && !is_dir {
+                continue;
+            }
+
+            let subject = if pattern.anchored { path } else { name };
+
+            if wildmatch(pattern.pattern.as_bytes(), subject.as_bytes()) {
+                ignored = !pattern.negated;
+            }
+        }
+
+        ignored
+    }
+}
+
+// `*` and `?` do not cross `/`, `**` does
+fn wildmatch(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = pattern[2..].strip_prefix(b"/").unwrap_or(&pattern[2..]);
+
+            (0..=text.len()).any(|i| wildmatch(rest, &text[i..]))
+        }
+        Some(b'*') => {
+            let rest = &pattern[1..];
+
+            for i in 0..=text.len() {
+                if wildmatch(rest, &text[i..]) {
+                    return true;
+                }
+
+                if i < text.len() && text[i] == b'/' {
+                    break;
+                }
+            }
+
+            false
+        }
+        Some(b'?') => !text.is_empty() && text[0] != b'/' && wildmatch(&pattern[1..], &text[1..]),
+        Some(c) => text.first() == Some(c) && wildmatch(&pattern[1..], &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(content: &str) -> IgnoreRules {
+        let mut rules = IgnoreRules::default();
+        rules.add_patterns(content);
+        rules
+    }
+
+    #[test]
+    fn test_ignore_basename_pattern() {
+        let rules = rules("*.log\n");
+
+        assert!(rules.is_ignored("debug.log", false));
+        assert!(rules.is_ignored("logs/debug.log", false));
+        assert!(!rules.is_ignored("debug.txt", false));
+    }
+
+    #[test]
+    fn test_ignore_directory_and_negation() {
+        let rules = rules("target/\n/build\n*.tmp\n!keep.tmp\n");
+
+        assert!(rules.is_ignored("target/debug/app", false));
+        assert!(!rules.is_ignored("target", false));
+        assert!(rules.is_ignored("build/out.o", false));
+        assert!(!rules.is_ignored("src/build", false));
+        assert!(rules.is_ignored("a.tmp", false));
+        assert!(!rules.is_ignored("keep.tmp", false));
+    }
+
+    #[test]
+    fn test_ignore_double_star() {
+        let rules = rules("docs/**/*.pdf\n");
+
+        assert!(rules.is_ignored("docs/a/b/c.pdf", false));
+        assert!(rules.is_ignored("docs/c.pdf", false));
+        assert!(!rules.is_ignored("src/c.pdf", false));
+    }
+}
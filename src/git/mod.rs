@@ -1,7 +1,16 @@
 pub mod config;
+pub mod filter;
 pub mod git_fs;
 pub mod head;
+pub mod identity;
+pub mod ignore;
 pub mod objects;
+pub mod pack;
+pub mod reflog;
 pub mod refs;
+pub mod refspec;
+pub mod repository;
+pub mod revision;
 pub mod traits;
 pub mod utils;
+pub mod walk;
@@ -1,5 +1,7 @@
 use crate::git::objects::header::{ObjectHeader, ObjectType};
 use crate::git::traits::ToBytes;
+use anyhow::{anyhow, bail, Error, Result};
+use std::str::FromStr;
 
 #[derive(Debug, Clone)]
 pub struct CommitObject {
@@ -13,6 +15,9 @@ pub struct CommitContent {
     pub parent_sha: Vec<String>,
     pub author: CommitAuthor,
     pub committer: CommitAuthor,
+    /// The armored signature of the `gpgsig` header, over the commit
+    /// without it.
+    pub signature: Option<String>,
     pub message: String,
 }
 
@@ -56,6 +61,18 @@ impl ToBytes for CommitAuthor {
     }
 }
 
+impl CommitContent {
+    /// The bytes a signature is made over: the commit without its `gpgsig`
+    /// header.
+    pub fn payload(&self) -> Vec<u8> {
+        CommitContent {
+            signature: None,
+            ..self.clone()
+        }
+        .to_bytes()
+    }
+}
+
 impl ToBytes for CommitContent {
     fn to_bytes(&self) -> Vec<u8> {
         let mut result = Vec::new();
@@ -69,12 +86,116 @@ impl ToBytes for CommitContent {
         result.extend_from_slice(self.author.to_bytes().as_slice());
         result.extend_from_slice("\ncommitter ".to_string().as_bytes());
         result.extend_from_slice(self.committer.to_bytes().as_slice());
+
+        // multi-line header values continue on lines starting with a space
+        if let Some(signature) = &self.signature {
+            result.extend_from_slice(
+                format!("\ngpgsig {}", signature.trim_end().replace('\n', "\n ")).as_bytes(),
+            );
+        }
+
         result.extend_from_slice(format!("\n\n{}\n", self.message).as_bytes());
 
         result
     }
 }
 
+impl FromStr for CommitAuthor {
+    type Err = Error;
+
+    // Name <email> timestamp timezone
+    fn from_str(s: &str) -> Result<Self> {
+        let open = s
+            .find('<')
+            .ok_or_else(|| anyhow!("Invalid identity: {}", s))?;
+        let close = s
+            .rfind('>')
+            .ok_or_else(|| anyhow!("Invalid identity: {}", s))?;
+
+        if close < open {
+            bail!("Invalid identity: {}", s);
+        }
+
+        let name = s[..open].trim().to_string();
+        let email = s[open + 1..close].to_string();
+
+        let mut date = s[close + 1..].split_whitespace();
+
+        let timestamp = date
+            .next()
+            .ok_or_else(|| anyhow!("Missing timestamp: {}", s))?
+            .parse()?;
+        let timezone = date.next().unwrap_or("+0000").to_string();
+
+        Ok(CommitAuthor {
+            name,
+            email,
+            timestamp,
+            timezone,
+        })
+    }
+}
+
+impl TryFrom<Vec<u8>> for CommitObject {
+    type Error = Error;
+
+    fn try_from(data: Vec<u8>) -> Result<Self> {
+        let header = ObjectHeader::try_from(data.clone())?;
+
+        if header.object_type != ObjectType::Commit {
+            bail!("Not a commit object: {}", header.object_type);
+        }
+
+        let body = String::from_utf8(data[header.header_size()..].to_vec())?;
+
+        let (headers, message) = body.split_once("\n\n").unwrap_or((body.as_str(), ""));
+
+        let mut tree_sha = None;
+        let mut parent_sha = Vec::new();
+        let mut author = None;
+        let mut committer = None;
+        let mut signature: Option<String> = None;
+        let mut in_signature = false;
+
+        for line in headers.lines() {
+            if let Some(continuation) = line.strip_prefix(' ') {
+                if let (true, Some(signature)) = (in_signature, signature.as_mut()) {
+                    signature.push('\n');
+                    signature.push_str(continuation);
+                }
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+
+            in_signature = key == "gpgsig";
+
+            match key {
+                "tree" => tree_sha = Some(value.to_string()),
+                "parent" => parent_sha.push(value.to_string()),
+                "author" => author = Some(value.parse()?),
+                "committer" => committer = Some(value.parse()?),
+                "gpgsig" => signature = Some(value.to_string()),
+                _ => {}
+            }
+        }
+
+        let message = message.strip_suffix('\n').unwrap_or(message).to_string();
+
+        Ok(CommitObject {
+            header,
+            content: CommitContent {
+                tree_sha: tree_sha.ok_or_else(|| anyhow!("Commit without tree"))?,
+                parent_sha,
+                author: author.ok_or_else(|| anyhow!("Commit without author"))?,
+                committer: committer.ok_or_else(|| anyhow!("Commit without committer"))?,
+                signature,
+                message,
+            },
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,6 +229,7 @@ mod tests {
             parent_sha: vec![String::from("def456"), String::from("ghi789")],
             author: author.clone(),
             committer: author.clone(),
+            signature: None,
             message: String::from("Initial commit"),
         };
 
@@ -131,6 +253,7 @@ mod tests {
             parent_sha: vec![String::from("def456"), String::from("ghi789")],
             author: author.clone(),
             committer: author.clone(),
+            signature: None,
             message: String::from("Initial commit"),
         };
 
@@ -149,4 +272,66 @@ mod tests {
 
         assert_eq!(commit_object.to_bytes(), expected);
     }
+
+    #[test]
+    fn test_commit_object_try_from() {
+        let author = CommitAuthor {
+            name: String::from("John Doe"),
+            email: String::from("john@example.com"),
+            timestamp: 1622519072,
+            timezone: String::from("+0200"),
+        };
+
+        let commit_object = CommitObject::new(CommitContent {
+            tree_sha: String::from("abc123"),
+            parent_sha: vec![String::from("def456")],
+            author: author.clone(),
+            committer: author.clone(),
+            signature: None,
+            message: String::from("Initial commit"),
+        });
+
+        let parsed = CommitObject::try_from(commit_object.to_bytes()).unwrap();
+
+        assert_eq!(parsed.content.tree_sha, "abc123");
+        assert_eq!(parsed.content.parent_sha, vec!["def456".to_string()]);
+        assert_eq!(parsed.content.author.name, "John Doe");
+        assert_eq!(parsed.content.author.timezone, "+0200");
+        assert_eq!(parsed.content.message, "Initial commit");
+        assert_eq!(parsed.to_bytes(), commit_object.to_bytes());
+    }
+
+    #[test]
+    fn test_commit_signature_header() {
+        let author = CommitAuthor {
+            name: String::from("John Doe"),
+            email: String::from("john@example.com"),
+            timestamp: 1622519072,
+            timezone: String::from("+0000"),
+        };
+
+        let content = CommitContent {
+            tree_sha: String::from("abc123"),
+            parent_sha: vec![],
+            author: author.clone(),
+            committer: author.clone(),
+            signature: Some(String::from("-----BEGIN-----\nabcd\n-----END-----")),
+            message: String::from("Signed commit"),
+        };
+
+        let bytes = content.to_bytes();
+        assert!(String::from_utf8(bytes)
+            .unwrap()
+            .contains("\ngpgsig -----BEGIN-----\n abcd\n -----END-----\n\nSigned commit\n"));
+
+        let commit_object = CommitObject::new(content.clone());
+        let parsed = CommitObject::try_from(commit_object.to_bytes()).unwrap();
+
+        assert_eq!(parsed.content.signature, content.signature);
+        assert_eq!(parsed.content.payload(), content.payload());
+        assert_eq!(parsed.to_bytes(), commit_object.to_bytes());
+        assert!(!String::from_utf8(content.payload())
+            .unwrap()
+            .contains("gpgsig"));
+    }
 }
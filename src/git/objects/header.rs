@@ -12,6 +12,7 @@ pub enum ObjectType {
     Blob,
     Tree,
     Commit,
+    Tag,
 }
 
 impl std::fmt::Display for ObjectType {
@@ -20,6 +21,21 @@ impl std::fmt::Display for ObjectType {
             ObjectType::Blob => write!(f, "blob"),
             ObjectType::Tree => write!(f, "tree"),
             ObjectType::Commit => write!(f, "commit"),
+            ObjectType::Tag => write!(f, "tag"),
+        }
+    }
+}
+
+impl std::str::FromStr for ObjectType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "blob" => Ok(ObjectType::Blob),
+            "tree" => Ok(ObjectType::Tree),
+            "commit" => Ok(ObjectType::Commit),
+            "tag" => Ok(ObjectType::Tag),
+            _ => anyhow::bail!("Invalid object type: {}", s),
         }
     }
 }
@@ -55,19 +71,16 @@ impl TryFrom<Vec<u8>> for ObjectHeader {
 
     fn try_from(value: Vec<u8>) -> Result<Self> {
         let object_type = {
-            if &value[..ObjectType::Blob.len()] == ObjectType::Blob.to_string().as_bytes() {
+            if value.starts_with(ObjectType::Blob.to_string().as_bytes()) {
                 ObjectType::Blob
-            } else if &value[..ObjectType::Tree.len()] == ObjectType::Tree.to_string().as_bytes() {
+            } else if value.starts_with(ObjectType::Tree.to_string().as_bytes()) {
                 ObjectType::Tree
-            } else if &value[..ObjectType::Commit.len()]
-                == ObjectType::Commit.to_string().as_bytes()
-            {
+            } else if value.starts_with(ObjectType::Commit.to_string().as_bytes()) {
                 ObjectType::Commit
+            } else if value.starts_with(ObjectType::Tag.to_string().as_bytes()) {
+                ObjectType::Tag
             } else {
-                anyhow::bail!(
-                    "Invalid object header: {}",
-                    std::str::from_utf8(&value).unwrap()
-                );
+                anyhow::bail!("Invalid object header: {}", String::from_utf8_lossy(&value));
             }
         };
 
@@ -80,10 +93,7 @@ impl TryFrom<Vec<u8>> for ObjectHeader {
                 size *= 10;
                 size += <u8 as Into<usize>>::into(*item - b'0');
             } else {
-                anyhow::bail!(
-                    "Invalid object header: {}",
-                    std::str::from_utf8(&value).unwrap()
-                );
+                anyhow::bail!("Invalid object header: {}", String::from_utf8_lossy(&value));
             }
         }
 
@@ -105,6 +115,14 @@ mod tests {
         assert_eq!(header.to_bytes(), b"blob 10\0");
     }
 
+    #[test]
+    fn test_object_header_try_from_tag() {
+        let header = ObjectHeader::try_from(b"tag 3\0abc".to_vec()).unwrap();
+
+        assert_eq!(header.object_type, ObjectType::Tag);
+        assert_eq!(header.size, 3);
+    }
+
     #[test]
     fn test_object_header_try_from() {
         let header = ObjectHeader {
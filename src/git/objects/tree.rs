@@ -31,14 +31,15 @@ impl TreeObject {
             let entry = entry?;
 
             // if it's .git directory, skip it
-            let path = entry.path();
-            let path = path.to_str().unwrap();
-            if path.contains(".git") {
+            if entry.file_name() == ".git" {
                 continue;
             }
 
             let entry_path = entry.path();
-            let entry_name = entry_path.file_name().unwrap().to_str().unwrap();
+            let entry_name = entry.file_name();
+            let Some(entry_name) = entry_name.to_str() else {
+                bail!("Invalid file name: {}", entry_path.display());
+            };
 
             let metadata = entry.metadata()?;
 
@@ -76,6 +77,29 @@ impl TreeObject {
         Ok(tree)
     }
 
+    // return the vector of (path, entry) for every non-tree entry
+    pub fn get_entries_recursive(&self, prefix: &str) -> Result<Vec<(String, TreeEntry)>> {
+        let mut result = Vec::new();
+
+        for entry in &self.entries {
+            let path = if prefix.is_empty() {
+                entry.name.clone()
+            } else {
+                format!("{}/{}", prefix, entry.name)
+            };
+
+            if entry.object_type() == ObjectType::Tree {
+                let tree = get_tree_object(&entry.hash)?;
+
+                result.extend(tree.get_entries_recursive(&path)?);
+            } else {
+                result.push((path, entry.clone()));
+            }
+        }
+
+        Ok(result)
+    }
+
     // return the vector of (path, data)
     #[async_recursion::async_recursion]
     pub async fn get_files_recursive(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>> {
@@ -121,8 +145,16 @@ impl TryFrom<Vec<u8>> for TreeEntry {
     type Error = Error;
 
     fn try_from(value: Vec<u8>) -> Result<Self> {
-        let space_index = value.iter().position(|&x| x == b' ').unwrap();
-        let null_index = value.iter().position(|&x| x == 0).unwrap();
+        let (Some(space_index), Some(null_index)) = (
+            value.iter().position(|&x| x == b' '),
+            value.iter().position(|&x| x == 0),
+        ) else {
+            bail!("Invalid tree entry");
+        };
+
+        if value.len() < null_index + 21 {
+            bail!("Truncated tree entry");
+        }
 
         let mode = std::str::from_utf8(&value[..space_index])?.parse()?;
         let name = std::str::from_utf8(&value[space_index + 1..null_index])?.to_string();
@@ -133,6 +165,14 @@ impl TryFrom<Vec<u8>> for TreeEntry {
 }
 
 impl TreeEntry {
+    pub fn object_type(&self) -> ObjectType {
+        match self.mode {
+            40000 => ObjectType::Tree,
+            160000 => ObjectType::Commit,
+            _ => ObjectType::Blob,
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.to_bytes().len()
     }
@@ -0,0 +1,581 @@
+use crate::git::objects::header::ObjectType;
+use anyhow::{anyhow, bail, Result};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+const IDX_V2_MAGIC: [u8; 4] = [0xff, b't', b'O', b'c'];
+
+const OBJ_OFS_DELTA: u8 = 6;
+const OBJ_REF_DELTA: u8 = 7;
+
+/// A packfile in `objects/pack`, with its `.idx` loaded in memory and the
+/// `.pack` kept open for reads.
+#[derive(Debug)]
+pub struct Pack {
+    file: Mutex<BufReader<File>>,
+    hashes: Vec<[u8; 20]>,
+    offsets: Vec<u64>,
+}
+
+fn read_u32(data: &[u8], at: usize) -> Result<u32> {
+    data.get(at..at + 4)
+        .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("Truncated pack index"))
+}
+
+fn read_u64(data: &[u8], at: usize) -> Result<u64> {
+    data.get(at..at + 8)
+        .map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("Truncated pack index"))
+}
+
+fn read_hash(data: &[u8], at: usize) -> Result<[u8; 20]> {
+    data.get(at..at + 20)
+        .map(|bytes| bytes.try_into().unwrap())
+        .ok_or_else(|| anyhow!("Truncated pack index"))
+}
+
+fn read_byte(reader: &mut impl Read) -> Result<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+
+    Ok(byte[0])
+}
+
+fn type_id(object_type: &ObjectType) -> u8 {
+    match object_type {
+        ObjectType::Commit => 1,
+        ObjectType::Tree => 2,
+        ObjectType::Blob => 3,
+        ObjectType::Tag => 4,
+    }
+}
+
+fn object_type(type_id: u8) -> Result<ObjectType> {
+    match type_id {
+        1 => Ok(ObjectType::Commit),
+        2 => Ok(ObjectType::Tree),
+        3 => Ok(ObjectType::Blob),
+        4 => Ok(ObjectType::Tag),
+        _ => bail!("Invalid packed object type: {}", type_id),
+    }
+}
+
+// size of the source or target in a delta header
+fn read_delta_size(delta: &[u8], pos: &mut usize) -> Result<usize> {
+    let mut size = 0usize;
+    let mut shift = 0;
+
+    loop {
+        let byte = *delta
+            .get(*pos)
+            .ok_or_else(|| anyhow!("Truncated delta header"))?;
+        *pos += 1;
+
+        size |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Ok(size);
+        }
+    }
+}
+
+/// Rebuild an object from its delta base and a git delta: a sequence of
+/// copy-from-base and insert-literal instructions.
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>> {
+    let mut pos = 0;
+
+    if read_delta_size(delta, &mut pos)? != base.len() {
+        bail!("Delta base size mismatch");
+    }
+
+    let target_size = read_delta_size(delta, &mut pos)?;
+    let mut result = Vec::with_capacity(target_size);
+
+    while pos < delta.len() {
+        let instruction = delta[pos];
+        pos += 1;
+
+        if instruction & 0x80 != 0 {
+            let mut offset = 0usize;
+            let mut size = 0usize;
+
+            for i in 0..4 {
+                if instruction & (1 << i) != 0 {
+                    offset |= (*delta.get(pos).unwrap_or(&0) as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+
+            for i in 0..3 {
+                if instruction & (0x10 << i) != 0 {
+                    size |= (*delta.get(pos).unwrap_or(&0) as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+
+            if size == 0 {
+                size = 0x10000;
+            }
+
+            let chunk = base
+                .get(offset..offset + size)
+                .ok_or_else(|| anyhow!("Delta copy out of range"))?;
+
+            result.extend_from_slice(chunk);
+        } else if instruction != 0 {
+            let size = instruction as usize;
+
+            let chunk = delta
+                .get(pos..pos + size)
+                .ok_or_else(|| anyhow!("Delta insert out of range"))?;
+
+            result.extend_from_slice(chunk);
+            pos += size;
+        } else {
+            bail!("Invalid delta instruction");
+        }
+    }
+
+    if result.len() != target_size {
+        bail!("Delta target size mismatch");
+    }
+
+    Ok(result)
+}
+
+impl Pack {
+    /// Load the `.idx` (version 1 or 2) of a `.pack` file.
+    pub fn open(pack_path: &Path) -> Result<Self> {
+        let idx = std::fs::read(pack_path.with_extension("idx"))?;
+
+        let (fanout_at, version) = if idx.starts_with(&IDX_V2_MAGIC) {
+            (8, read_u32(&idx, 4)?)
+        } else {
+            (0, 1)
+        };
+
+        if version > 2 {
+            bail!("Unsupported pack index version: {}", version);
+        }
+
+        let count = read_u32(&idx, fanout_at + 255 * 4)? as usize;
+        let entries_at = fanout_at + 256 * 4;
+
+        let mut hashes = Vec::with_capacity(count);
+        let mut offsets = Vec::with_capacity(count);
+
+        if version == 1 {
+            // 4-byte offset followed by the hash
+            for i in 0..count {
+                offsets.push(read_u32(&idx, entries_at + i * 24)? as u64);
+                hashes.push(read_hash(&idx, entries_at + i * 24 + 4)?);
+            }
+        } else {
+            let offsets_at = entries_at + count * 24;
+            let large_offsets_at = offsets_at + count * 4;
+
+            for i in 0..count {
+                hashes.push(read_hash(&idx, entries_at + i * 20)?);
+
+                let offset = read_u32(&idx, offsets_at + i * 4)?;
+
+                // the MSB marks an index into the 8-byte offset table
+                offsets.push(if offset & 0x8000_0000 != 0 {
+                    read_u64(&idx, large_offsets_at + (offset & 0x7fff_ffff) as usize * 8)?
+                } else {
+                    offset as u64
+                });
+            }
+        }
+
+        Ok(Pack {
+            file: Mutex::new(BufReader::new(File::open(pack_path)?)),
+            hashes,
+            offsets,
+        })
+    }
+
+    /// Hashes of every object in the pack.
+    pub fn hashes(&self) -> impl Iterator<Item = String> + '_ {
+        self.hashes.iter().map(hex::encode)
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.find(hash).is_some()
+    }
+
+    fn find(&self, hash: &str) -> Option<u64> {
+        let hash: [u8; 20] = hex::decode(hash.trim()).ok()?.try_into().ok()?;
+
+        self.hashes
+            .binary_search(&hash)
+            .ok()
+            .map(|index| self.offsets[index])
+    }
+
+    /// The type and content of an object, with deltas resolved.
+    pub fn read(&self, hash: &str) -> Result<Option<(ObjectType, Vec<u8>)>> {
+        match self.find(hash) {
+            Some(offset) => {
+                let mut reader = self
+                    .file
+                    .lock()
+                    .map_err(|_| anyhow!("Pack file lock poisoned"))?;
+
+                self.read_at(&mut reader, offset).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn read_at(&self, reader: &mut BufReader<File>, offset: u64) -> Result<(ObjectType, Vec<u8>)> {
+        reader.seek(SeekFrom::Start(offset))?;
+
+        // type and inflated size: 3 type bits, then 4 + 7n size bits
+        let mut byte = read_byte(reader)?;
+        let type_id = (byte >> 4) & 0x07;
+
+        while byte & 0x80 != 0 {
+            byte = read_byte(reader)?;
+        }
+
+        match type_id {
+            OBJ_OFS_DELTA => {
+                let mut byte = read_byte(reader)?;
+                let mut distance = (byte & 0x7f) as u64;
+
+                while byte & 0x80 != 0 {
+                    byte = read_byte(reader)?;
+                    distance = ((distance + 1) << 7) | (byte & 0x7f) as u64;
+                }
+
+                let delta = inflate(reader)?;
+
+                let base_offset = offset
+                    .checked_sub(distance)
+                    .ok_or_else(|| anyhow!("Invalid delta base offset"))?;
+
+                let (object_type, base) = self.read_at(reader, base_offset)?;
+
+                Ok((object_type, apply_delta(&base, &delta)?))
+            }
+            OBJ_REF_DELTA => {
+                let mut base_hash = [0u8; 20];
+                reader.read_exact(&mut base_hash)?;
+
+                let delta = inflate(reader)?;
+
+                let base_hash = hex::encode(base_hash);
+
+                let Some(base_offset) = self.find(&base_hash) else {
+                    bail!("Delta base {} is not in the pack", base_hash);
+                };
+
+                let (object_type, base) = self.read_at(reader, base_offset)?;
+
+                Ok((object_type, apply_delta(&base, &delta)?))
+            }
+            type_id => Ok((object_type(type_id)?, inflate(reader)?)),
+        }
+    }
+}
+
+fn inflate(reader: &mut impl Read) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    ZlibDecoder::new(reader).read_to_end(&mut data)?;
+
+    Ok(data)
+}
+
+/// Every pack in `<git_dir>/objects/pack`.
+pub fn find_packs(git_dir: &Path) -> Result<Vec<Pack>> {
+    let pack_dir = git_dir.join("objects").join("pack");
+
+    if !pack_dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut packs = Vec::new();
+
+    for entry in std::fs::read_dir(pack_dir)? {
+        let path = entry?.path();
+
+        if path.extension().and_then(|ext| ext.to_str()) == Some("pack")
+            && path.with_extension("idx").is_file()
+        {
+            packs.push(Pack::open(&path)?);
+        }
+    }
+
+    Ok(packs)
+}
+
+/// Read an object of one of `packs` as its loose form
+/// (`<type> <size>\0<content>`).
+pub fn read_packed_object(packs: &[Pack], hash: &str) -> Result<Option<Vec<u8>>> {
+    for pack in packs {
+        if let Some((object_type, content)) = pack.read(hash)? {
+            let mut data = format!("{} {}\0", object_type, content.len()).into_bytes();
+            data.extend(content);
+
+            return Ok(Some(data));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Write a version 2 packfile of undeltified objects, each given as its
+/// type and content.
+pub fn write_pack(objects: &[(ObjectType, Vec<u8>)], output: &mut impl Write) -> Result<()> {
+    let mut pack = Vec::new();
+
+    pack.extend_from_slice(b"PACK");
+    pack.extend_from_slice(&2u32.to_be_bytes());
+    pack.extend_from_slice(&(objects.len() as u32).to_be_bytes());
+
+    for (object_type, content) in objects {
+        // type and size: 3 type bits and 4 size bits, then 7 bits per byte
+        let mut size = content.len();
+        let mut byte = (type_id(object_type) << 4) | (size & 0x0f) as u8;
+        size >>= 4;
+
+        while size > 0 {
+            pack.push(byte | 0x80);
+            byte = (size & 0x7f) as u8;
+            size >>= 7;
+        }
+
+        pack.push(byte);
+
+        let mut z = ZlibEncoder::new(Vec::new(), Compression::default());
+        z.write_all(content)?;
+        pack.extend(z.finish()?);
+    }
+
+    let mut hasher = Sha1::new();
+    hasher.update(&pack);
+    let checksum = hasher.finalize();
+
+    output.write_all(&pack)?;
+    output.write_all(&checksum)?;
+
+    Ok(())
+}
+
+pub fn has_packed_object(packs: &[Pack], hash: &str) -> bool {
+    packs.iter().any(|pack| pack.contains(hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::repository::{hash_object_data, Repository};
+    use std::path::PathBuf;
+
+    fn blob_hash(content: &[u8]) -> String {
+        hash_object_data(&[format!("blob {}\0", content.len()).as_bytes(), content].concat())
+    }
+
+    // a pack entry: type and size, `base` (the delta base if any), then
+    // the compressed content
+    fn entry(type_id: u8, content: &[u8], base: &[u8]) -> Vec<u8> {
+        let mut entry = Vec::new();
+        let mut size = content.len();
+        let mut byte = (type_id << 4) | (size & 0x0f) as u8;
+        size >>= 4;
+
+        while size > 0 {
+            entry.push(byte | 0x80);
+            byte = (size & 0x7f) as u8;
+            size >>= 7;
+        }
+
+        entry.push(byte);
+        entry.extend_from_slice(base);
+
+        let mut z = ZlibEncoder::new(Vec::new(), Compression::default());
+        z.write_all(content).unwrap();
+        entry.extend(z.finish().unwrap());
+
+        entry
+    }
+
+    // write `pack-test.pack` of `entries` in `dir`, with an index of the
+    // given version
+    fn write_fixture(dir: &Path, entries: &[(String, Vec<u8>)], idx_version: u32) -> PathBuf {
+        let mut pack = b"PACK".to_vec();
+        pack.extend(2u32.to_be_bytes());
+        pack.extend((entries.len() as u32).to_be_bytes());
+
+        let mut index = Vec::new();
+
+        for (hash, entry) in entries {
+            index.push((hex::decode(hash).unwrap(), pack.len() as u32));
+            pack.extend(entry);
+        }
+
+        let checksum = Sha1::digest(&pack).to_vec();
+        pack.extend(&checksum);
+        index.sort();
+
+        let mut idx = Vec::new();
+
+        if idx_version == 2 {
+            idx.extend(IDX_V2_MAGIC);
+            idx.extend(2u32.to_be_bytes());
+        }
+
+        for byte in 0..=255u8 {
+            let count = index.iter().filter(|(hash, _)| hash[0] <= byte).count();
+            idx.extend((count as u32).to_be_bytes());
+        }
+
+        if idx_version == 1 {
+            for (hash, offset) in &index {
+                idx.extend(offset.to_be_bytes());
+                idx.extend(hash);
+            }
+        } else {
+            for (hash, _) in &index {
+                idx.extend(hash);
+            }
+
+            // CRC32s, which reads don't check
+            idx.extend(vec![0u8; index.len() * 4]);
+
+            for (_, offset) in &index {
+                idx.extend(offset.to_be_bytes());
+            }
+        }
+
+        idx.extend(&checksum);
+        let idx_checksum = Sha1::digest(&idx).to_vec();
+        idx.extend(idx_checksum);
+
+        std::fs::create_dir_all(dir).unwrap();
+
+        let path = dir.join("pack-test.pack");
+        std::fs::write(&path, pack).unwrap();
+        std::fs::write(path.with_extension("idx"), idx).unwrap();
+
+        path
+    }
+
+    // a blob, an offset delta and a ref delta against it
+    fn fixture_entries() -> Vec<(String, Vec<u8>)> {
+        let base = entry(3, b"hello world", &[]);
+
+        // copy 6 bytes from 0, insert "rust!!!!"
+        let mut ofs_delta = vec![11, 14, 0x80 | 0x01 | 0x10, 0, 6, 8];
+        ofs_delta.extend_from_slice(b"rust!!!!");
+
+        // copy 11 bytes from 0, insert ", again"
+        let mut ref_delta = vec![11, 18, 0x80 | 0x10, 11, 7];
+        ref_delta.extend_from_slice(b", again");
+
+        vec![
+            (blob_hash(b"hello world"), base.clone()),
+            // the distance back to the base, the entry right before, in one byte
+            (
+                blob_hash(b"hello rust!!!!"),
+                entry(OBJ_OFS_DELTA, &ofs_delta, &[base.len() as u8]),
+            ),
+            (
+                blob_hash(b"hello world, again"),
+                entry(
+                    OBJ_REF_DELTA,
+                    &ref_delta,
+                    &hex::decode(blob_hash(b"hello world")).unwrap(),
+                ),
+            ),
+        ]
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dgit-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        dir
+    }
+
+    #[test]
+    fn test_read_packed_objects() {
+        let dir = temp_dir("pack");
+
+        for idx_version in [1, 2] {
+            let path = write_fixture(
+                &dir.join(format!("v{}", idx_version)),
+                &fixture_entries(),
+                idx_version,
+            );
+            let packs = vec![Pack::open(&path).unwrap()];
+
+            for content in [
+                &b"hello world"[..],
+                b"hello rust!!!!",
+                b"hello world, again",
+            ] {
+                assert_eq!(
+                    packs[0].read(&blob_hash(content)).unwrap(),
+                    Some((ObjectType::Blob, content.to_vec()))
+                );
+            }
+
+            assert_eq!(packs[0].hashes().count(), 3);
+            assert!(!has_packed_object(&packs, &blob_hash(b"missing")));
+            assert_eq!(
+                read_packed_object(&packs, &blob_hash(b"hello rust!!!!")).unwrap(),
+                Some(b"blob 14\0hello rust!!!!".to_vec())
+            );
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_pack_written_after_lookup() {
+        let dir = temp_dir("pack-cache");
+        let repository = Repository::init_bare(&dir, "main").unwrap();
+        let hash = blob_hash(b"hello world");
+
+        // loads the (empty) list of packs
+        assert!(!repository.has_object(&hash));
+
+        write_fixture(
+            &repository.objects_dir().join("pack"),
+            &fixture_entries(),
+            2,
+        );
+
+        assert!(!repository.has_object(&hash));
+        assert_eq!(
+            repository.read_object(&hash).unwrap(),
+            b"blob 11\0hello world"
+        );
+        assert!(repository.has_object(&hash));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_delta() {
+        let base = b"hello world";
+
+        // base size 11, target size 14, copy 6 bytes from 0, insert "rust!!!!"
+        let mut delta = vec![11, 14, 0x80 | 0x01 | 0x10, 0, 6, 8];
+        delta.extend_from_slice(b"rust!!!!");
+
+        assert_eq!(apply_delta(base, &delta).unwrap(), b"hello rust!!!!");
+
+        assert!(apply_delta(b"short", &delta).is_err());
+    }
+}
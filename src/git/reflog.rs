@@ -0,0 +1,162 @@
+use crate::git::identity::{now, reflog_identity};
+use crate::git::objects::commit::CommitAuthor;
+use crate::git::repository::Repository;
+use crate::git::traits::ToBytes;
+use anyhow::{anyhow, bail, Result};
+use std::io::Write;
+use std::str::FromStr;
+
+/// The value of a ref before it was created.
+pub const NULL_HASH: &str = "0000000000000000000000000000000000000000";
+
+/// A line of `.git/logs/<ref>`: a change of the ref, who made it and why.
+#[derive(Debug, Clone)]
+pub struct ReflogEntry {
+    pub old: String,
+    pub new: String,
+    pub committer: CommitAuthor,
+    pub message: String,
+}
+
+impl ReflogEntry {
+    // `<old> <new> <name> <<email>> <timestamp> <timezone>\t<message>`
+    pub fn to_line(&self) -> String {
+        format!(
+            "{} {} {}\t{}\n",
+            self.old,
+            self.new,
+            String::from_utf8_lossy(&self.committer.to_bytes()),
+            self.message.lines().next().unwrap_or_default()
+        )
+    }
+}
+
+impl FromStr for ReflogEntry {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let (header, message) = line.split_once('\t').unwrap_or((line, ""));
+
+        let mut parts = header.splitn(3, ' ');
+        let old = parts.next().unwrap_or_default();
+        let new = parts
+            .next()
+            .ok_or_else(|| anyhow!("Invalid reflog entry: {}", line))?;
+        let committer = parts
+            .next()
+            .ok_or_else(|| anyhow!("Invalid reflog entry: {}", line))?;
+
+        Ok(ReflogEntry {
+            old: old.to_string(),
+            new: new.to_string(),
+            committer: CommitAuthor::from_str(committer)?,
+            message: message.trim_end().to_string(),
+        })
+    }
+}
+
+// like `core.logAllRefUpdates`: HEAD, branches and remote-tracking refs,
+// and any other ref that already has a log
+fn should_log(repository: &Repository, name: &str) -> bool {
+    name == "HEAD"
+        || name.starts_with("refs/heads/")
+        || name.starts_with("refs/remotes/")
+        || repository.git_dir().join("logs").join(name).is_file()
+}
+
+/// Record a change of `name` from `old` to `new`.
+pub fn append_reflog(
+    repository: &Repository,
+    name: &str,
+    old: &str,
+    new: &str,
+    message: &str,
+) -> Result<()> {
+    if !should_log(repository, name) || old == new {
+        return Ok(());
+    }
+
+    let path = repository.git_dir().join("logs").join(name);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let entry = ReflogEntry {
+        old: if old.is_empty() { NULL_HASH } else { old }.to_string(),
+        new: new.to_string(),
+        committer: reflog_identity().signature(now()),
+        message: message.to_string(),
+    };
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+
+    file.write_all(entry.to_line().as_bytes())?;
+
+    Ok(())
+}
+
+/// The changes of `name`, oldest first.
+pub fn read_reflog(repository: &Repository, name: &str) -> Result<Vec<ReflogEntry>> {
+    let path = repository.git_dir().join("logs").join(name);
+
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(ReflogEntry::from_str)
+        .collect()
+}
+
+/// The value of `name@{n}`: what `name` was `n` changes ago.
+pub fn reflog_value(entries: &[ReflogEntry], name: &str, n: usize) -> Result<String> {
+    if entries.is_empty() {
+        bail!("Log for {} is empty", name);
+    }
+
+    match entries.iter().rev().nth(n) {
+        Some(entry) => Ok(entry.new.clone()),
+        None => bail!("Log for {} only has {} entries", name, entries.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reflog_entry() {
+        let line = format!(
+            "{} b6fc4c620b67d95f953a5c1c1230aaab5db5a1b0 John Doe <john@example.com> 1622519072 +0200\tcommit (initial): first\n",
+            NULL_HASH
+        );
+
+        let entry = ReflogEntry::from_str(line.trim_end()).unwrap();
+
+        assert_eq!(entry.old, NULL_HASH);
+        assert_eq!(entry.committer.email, "john@example.com");
+        assert_eq!(entry.committer.timezone, "+0200");
+        assert_eq!(entry.message, "commit (initial): first");
+        assert_eq!(entry.to_line(), line);
+
+        let second = ReflogEntry {
+            old: entry.new.clone(),
+            new: "a".repeat(40),
+            ..entry.clone()
+        };
+        let entries = vec![entry, second];
+
+        assert_eq!(reflog_value(&entries, "HEAD", 0).unwrap(), "a".repeat(40));
+        assert_eq!(
+            reflog_value(&entries, "HEAD", 1).unwrap(),
+            "b6fc4c620b67d95f953a5c1c1230aaab5db5a1b0"
+        );
+        assert!(reflog_value(&entries, "HEAD", 2).is_err());
+    }
+}
@@ -1,12 +1,50 @@
-use crate::git::utils::detect_git_dir;
-use anyhow::Result;
-use async_recursion::async_recursion;
+use crate::git::repository::Repository;
+use anyhow::{bail, Result};
 use std::path::Path;
-use walkdir::WalkDir;
 
 #[derive(Debug)]
 pub struct Ref(pub String);
 
+/// Check a full ref name against the rules of `git check-ref-format`.
+pub fn validate_ref_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.contains("..")
+        && !name.contains("//")
+        && !name.contains("@{")
+        && !name.ends_with('/')
+        && !name.ends_with('.')
+        && !name.ends_with(".lock")
+        && !name
+            .chars()
+            .any(|c| c.is_ascii_control() || " ~^:?*[\\".contains(c))
+        && !name
+            .split('/')
+            .any(|component| component.is_empty() || component.starts_with('.'));
+
+    if !valid {
+        bail!("Invalid ref name: '{}'", name);
+    }
+
+    Ok(())
+}
+
+/// The `(ref name, hash)` entries of `<git_dir>/packed-refs`.
+pub fn read_packed_refs(git_dir: &Path) -> Result<Vec<(String, String)>> {
+    let path = git_dir.join("packed-refs");
+
+    if !path.is_file() {
+        return Ok(vec![]);
+    }
+
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        // skip the header and the peeled values of annotated tags
+        .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+        .filter_map(|line| line.split_once(' '))
+        .map(|(hash, name)| (name.trim().to_string(), hash.to_string()))
+        .collect())
+}
+
 impl Ref {
     // get ref path
     pub fn new(ref_name: &str) -> Self {
@@ -15,39 +53,22 @@ impl Ref {
 
     // get ref content
     pub async fn get_content(&self) -> Result<Vec<u8>> {
-        let path = format!("./.git/{}", self.0);
+        let content = Repository::current()?.read_ref(&self.0)?;
 
-        let content = tokio::fs::read(path).await.unwrap_or(vec![]);
-
-        Ok(content)
+        Ok(content.unwrap_or_default().into_bytes())
     }
 
-    #[async_recursion]
     pub async fn resolve(&self) -> Result<String> {
-        let content = self.get_content().await?;
-
-        let content = String::from_utf8(content)?;
-
-        let content = Ref::parse_ref_content(&content);
-
-        if content.starts_with("refs/") {
-            let ref_ = Ref::new(&content);
-            ref_.resolve().await
-        } else {
-            Ok(content)
-        }
+        Repository::current()?.resolve_ref(&self.0)
     }
 
-    pub async fn update(&mut self, hash: &str) -> Result<()> {
-        let path = format!("./.git/{}", self.0);
-
-        // create parent directories if they don't exist
-        let parent = std::path::Path::new(&path).parent().unwrap();
-        let _ = tokio::fs::create_dir_all(parent).await;
-
-        tokio::fs::write(path, hash).await?;
+    /// Point the ref at `hash`, recording why in its reflog.
+    pub async fn update(&mut self, hash: &str, message: &str) -> Result<()> {
+        Repository::current()?.update_ref_logged(&self.0, hash, message)
+    }
 
-        Ok(())
+    pub async fn delete(&self) -> Result<bool> {
+        Repository::current()?.delete_ref(&self.0)
     }
 
     pub fn parse_ref_content(content: &str) -> String {
@@ -61,37 +82,23 @@ impl Ref {
     }
 
     pub async fn get_all_refs() -> Result<Vec<(String, Vec<u8>)>> {
-        // find .git directory
-        let git_dir = detect_git_dir()?;
-
-        let refs = format!("{}/refs", git_dir);
-
-        let mut result = Vec::new();
-
-        for entry in WalkDir::new(Path::new(&refs))
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-
-            if path.is_dir() {
-                continue;
-            }
-
-            let path = path.to_str().unwrap();
-            let path = path.replace("\\", "/");
-
-            let path = path.split('/').collect::<Vec<&str>>();
-
-            let ref_path = path.join("/");
+        Repository::current()?.refs()
+    }
+}
 
-            // get relative path
-            let ref_path = ref_path.split(".git/").collect::<Vec<&str>>()[1];
-            let content = tokio::fs::read(entry.path()).await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            result.push((ref_path.to_string(), content));
-        }
+    #[test]
+    fn test_validate_ref_name() {
+        assert!(validate_ref_name("refs/heads/main").is_ok());
+        assert!(validate_ref_name("refs/heads/feature/x-1").is_ok());
 
-        Ok(result)
+        assert!(validate_ref_name("refs/heads/a..b").is_err());
+        assert!(validate_ref_name("refs/heads/with space").is_err());
+        assert!(validate_ref_name("refs/heads/.hidden").is_err());
+        assert!(validate_ref_name("refs/heads/main.lock").is_err());
+        assert!(validate_ref_name("refs/heads/").is_err());
     }
 }
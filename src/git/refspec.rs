@@ -0,0 +1,154 @@
+use anyhow::{bail, Error, Result};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A `[+]<src>:<dst>` mapping between ref names, where both sides may
+/// contain a single `*` wildcard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Refspec {
+    pub force: bool,
+    pub src: String,
+    pub dst: String,
+}
+
+impl Refspec {
+    pub fn new(force: bool, src: &str, dst: &str) -> Self {
+        Refspec {
+            force,
+            src: src.to_string(),
+            dst: dst.to_string(),
+        }
+    }
+
+    /// The default fetch refspec of a remote.
+    pub fn default_fetch(remote: &str) -> Self {
+        Refspec::new(true, "refs/heads/*", &format!("refs/remotes/{}/*", remote))
+    }
+
+    /// Branches and tags are pushed to the same names by default.
+    pub fn default_push() -> Vec<Self> {
+        vec![
+            Refspec::new(false, "refs/heads/*", "refs/heads/*"),
+            Refspec::new(false, "refs/tags/*", "refs/tags/*"),
+        ]
+    }
+
+    pub fn is_glob(&self) -> bool {
+        self.src.contains('*')
+    }
+
+    /// Map a ref matching the source side to the destination side.
+    pub fn map(&self, name: &str) -> Option<String> {
+        match_glob(&self.src, name).map(|matched| self.dst.replacen('*', matched, 1))
+    }
+
+    /// Map a ref matching the destination side back to the source side.
+    pub fn map_reverse(&self, name: &str) -> Option<String> {
+        match_glob(&self.dst, name).map(|matched| self.src.replacen('*', matched, 1))
+    }
+}
+
+// the part of `name` matched by the `*` of `pattern`
+fn match_glob<'a>(pattern: &str, name: &'a str) -> Option<&'a str> {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => name
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix)),
+        None => (pattern == name).then_some(""),
+    }
+}
+
+// `main` -> `refs/heads/main`, `tags/v1` -> `refs/tags/v1`
+pub fn expand_ref_name(name: &str) -> String {
+    if name.starts_with("refs/") || name == "HEAD" {
+        name.to_string()
+    } else if name.starts_with("heads/") || name.starts_with("tags/") {
+        format!("refs/{}", name)
+    } else {
+        format!("refs/heads/{}", name)
+    }
+}
+
+impl FromStr for Refspec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (force, spec) = match s.strip_prefix('+') {
+            Some(spec) => (true, spec),
+            None => (false, s),
+        };
+
+        let (src, dst) = spec.split_once(':').unwrap_or((spec, spec));
+
+        if src.is_empty() || dst.is_empty() {
+            bail!("Invalid refspec: {}", s);
+        }
+
+        if src.matches('*').count() > 1 || src.contains('*') != dst.contains('*') {
+            bail!("Invalid refspec pattern: {}", s);
+        }
+
+        Ok(Refspec::new(
+            force,
+            &expand_ref_name(src),
+            &expand_ref_name(dst),
+        ))
+    }
+}
+
+impl Display for Refspec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.force {
+            write!(f, "+")?;
+        }
+
+        write!(f, "{}:{}", self.src, self.dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refspec_from_str() {
+        let refspec = Refspec::from_str("+refs/heads/*:refs/remotes/origin/*").unwrap();
+
+        assert!(refspec.force);
+        assert_eq!(refspec.src, "refs/heads/*");
+        assert_eq!(refspec.dst, "refs/remotes/origin/*");
+        assert_eq!(refspec.to_string(), "+refs/heads/*:refs/remotes/origin/*");
+
+        let refspec = Refspec::from_str("main:dev").unwrap();
+
+        assert!(!refspec.force);
+        assert_eq!(refspec.src, "refs/heads/main");
+        assert_eq!(refspec.dst, "refs/heads/dev");
+
+        assert!(Refspec::from_str("refs/heads/*:refs/heads/main").is_err());
+        assert!(Refspec::from_str(":").is_err());
+    }
+
+    #[test]
+    fn test_refspec_map() {
+        let refspec = Refspec::default_fetch("origin");
+
+        assert_eq!(
+            refspec.map("refs/heads/feature/x").as_deref(),
+            Some("refs/remotes/origin/feature/x")
+        );
+        assert_eq!(refspec.map("refs/tags/v1"), None);
+        assert_eq!(
+            refspec.map_reverse("refs/remotes/origin/main").as_deref(),
+            Some("refs/heads/main")
+        );
+
+        let refspec = Refspec::from_str("main").unwrap();
+
+        assert_eq!(
+            refspec.map("refs/heads/main").as_deref(),
+            Some("refs/heads/main")
+        );
+        assert_eq!(refspec.map("refs/heads/main2"), None);
+    }
+}
@@ -0,0 +1,733 @@
+use crate::error::{self, Error};
+use crate::git::config::{Config, ConfigFile, ConfigKey};
+use crate::git::pack::{find_packs, has_packed_object, read_packed_object, Pack};
+use crate::git::reflog::append_reflog;
+use crate::git::refs::{read_packed_refs, validate_ref_name, Ref};
+use anyhow::{anyhow, bail, Result};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use sha1::{Digest, Sha1};
+use std::collections::BTreeSet;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use walkdir::WalkDir;
+
+/// A git repository on disk: its git directory and, unless it is bare, its
+/// work tree. Objects, refs and config are read and written through it
+/// instead of paths relative to the current directory.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    git_dir: PathBuf,
+    work_tree: Option<PathBuf>,
+    // the packs of `objects/pack`, loaded on the first lookup and again
+    // after `refresh_packs`
+    packs: Arc<RwLock<Option<Arc<Vec<Pack>>>>>,
+}
+
+/// The hash of an object given in its loose form.
+pub fn hash_object_data(data: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(data);
+
+    hex::encode(hasher.finalize())
+}
+
+/// Whether `hash` is a full object name: 40 lowercase hex digits.
+pub fn is_object_hash(hash: &str) -> bool {
+    hash.len() == 40 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn is_git_dir(path: &Path) -> bool {
+    path.join("HEAD").is_file() && path.join("objects").is_dir()
+}
+
+impl Repository {
+    pub fn new(git_dir: &Path, work_tree: Option<&Path>) -> Self {
+        Repository {
+            git_dir: git_dir.to_path_buf(),
+            work_tree: work_tree.map(Path::to_path_buf),
+            packs: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Open the repository of a work tree (containing `.git`) or a bare
+    /// repository.
+    pub fn open(path: &Path) -> error::Result<Self> {
+        if is_git_dir(&path.join(".git")) {
+            return Ok(Repository::new(&path.join(".git"), Some(path)));
+        }
+
+        if is_git_dir(path) {
+            return Ok(Repository::new(path, None));
+        }
+
+        Err(Error::NotARepository(path.to_path_buf()))
+    }
+
+    /// Open the repository `path` or any of its parents belongs to.
+    pub fn discover(path: &Path) -> error::Result<Self> {
+        let path = std::fs::canonicalize(path)?;
+
+        for dir in path.ancestors() {
+            if let Ok(repository) = Repository::open(dir) {
+                return Ok(repository);
+            }
+        }
+
+        Err(Error::NotARepository(path))
+    }
+
+    /// The repository of the current process: `GIT_DIR` (as set for remote
+    /// helpers) with `GIT_WORK_TREE`, or the one the current directory is in.
+    pub fn current() -> error::Result<Self> {
+        if let Ok(git_dir) = std::env::var("GIT_DIR") {
+            let git_dir = PathBuf::from(git_dir);
+
+            let work_tree = match std::env::var("GIT_WORK_TREE") {
+                Ok(work_tree) => Some(PathBuf::from(work_tree)),
+                Err(_) if git_dir.file_name().and_then(|name| name.to_str()) == Some(".git") => {
+                    git_dir.parent().map(Path::to_path_buf)
+                }
+                Err(_) => None,
+            };
+
+            return Ok(Repository::new(&git_dir, work_tree.as_deref()));
+        }
+
+        Repository::discover(&std::env::current_dir()?)
+    }
+
+    fn create(
+        git_dir: &Path,
+        work_tree: Option<&Path>,
+        initial_branch: &str,
+    ) -> Result<(Self, bool)> {
+        let repository = Repository::new(git_dir, work_tree);
+        let exists = git_dir.join("HEAD").is_file();
+
+        if !exists {
+            validate_ref_name(&format!("refs/heads/{}", initial_branch))?;
+        }
+
+        for dir in ["objects/info", "objects/pack", "refs/heads", "refs/tags"] {
+            std::fs::create_dir_all(git_dir.join(dir))?;
+        }
+
+        if !exists {
+            std::fs::write(
+                git_dir.join("HEAD"),
+                format!("ref: refs/heads/{}\n", initial_branch),
+            )?;
+
+            let mut config = ConfigFile::open(&repository.config_path())?;
+
+            config.set(&ConfigKey::from_str("core.repositoryformatversion")?, "0");
+            config.set(
+                &ConfigKey::from_str("core.bare")?,
+                &work_tree.is_none().to_string(),
+            );
+
+            config.save()?;
+        }
+
+        Ok((repository, exists))
+    }
+
+    /// Create a repository with an unborn `HEAD` on `initial_branch` in
+    /// `work_tree`, or open the existing one there. Returns whether it
+    /// already existed.
+    pub fn init(work_tree: &Path, initial_branch: &str) -> Result<(Self, bool)> {
+        let (repository, exists) =
+            Repository::create(&work_tree.join(".git"), Some(work_tree), initial_branch)?;
+
+        std::fs::create_dir_all(repository.git_dir.join("refs/remotes"))?;
+
+        Ok((repository, exists))
+    }
+
+    /// Create a bare repository in `git_dir` with an unborn `HEAD` on
+    /// `initial_branch`.
+    pub fn init_bare(git_dir: &Path, initial_branch: &str) -> Result<Self> {
+        Repository::create(git_dir, None, initial_branch).map(|(repository, _)| repository)
+    }
+
+    pub fn git_dir(&self) -> &Path {
+        &self.git_dir
+    }
+
+    pub fn is_bare(&self) -> bool {
+        self.work_tree.is_none()
+    }
+
+    pub fn work_tree(&self) -> Result<&Path> {
+        self.work_tree
+            .as_deref()
+            .ok_or_else(|| anyhow!("This operation must be run in a work tree"))
+    }
+
+    pub fn objects_dir(&self) -> PathBuf {
+        self.git_dir.join("objects")
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.git_dir.join("config")
+    }
+
+    /// The system and global config with this repository's config on top.
+    pub fn config(&self) -> Result<Config> {
+        Config::load_with_local(Some(&self.config_path()))
+    }
+
+    /// Where the object `hash` is stored in its loose form, or `None` if
+    /// `hash` isn't a full object name, so a hash coming from a remote can't
+    /// point outside `objects/`.
+    pub fn loose_object_path(&self, hash: &str) -> Option<PathBuf> {
+        let hash = hash.trim();
+
+        if !is_object_hash(hash) {
+            return None;
+        }
+
+        let (prefix, rest) = hash.split_at(2);
+
+        Some(self.objects_dir().join(prefix).join(rest))
+    }
+
+    /// Read an object in its loose form (`<type> <size>\0<content>`), from
+    /// `objects/` or from a pack.
+    pub fn read_object(&self, hash: &str) -> error::Result<Vec<u8>> {
+        let hash = hash.trim();
+
+        let Some(object_path) = self.loose_object_path(hash) else {
+            return Err(Error::InvalidObjectName(hash.to_string()));
+        };
+
+        let corrupt = |reason: String| Error::CorruptObject {
+            hash: hash.to_string(),
+            reason,
+        };
+
+        if !object_path.is_file() {
+            let packs = self.packs().map_err(|e| corrupt(e.to_string()))?;
+
+            if let Some(data) =
+                read_packed_object(&packs, hash).map_err(|e| corrupt(e.to_string()))?
+            {
+                return Ok(data);
+            }
+
+            // a pack written since the packs were loaded, like git does
+            // before giving up on an object
+            self.refresh_packs();
+
+            let packs = self.packs().map_err(|e| corrupt(e.to_string()))?;
+
+            return read_packed_object(&packs, hash)
+                .map_err(|e| corrupt(e.to_string()))?
+                .ok_or_else(|| Error::ObjectNotFound(hash.to_string()));
+        }
+
+        let data = std::fs::read(object_path)?;
+
+        // decompress the data
+        let mut z = ZlibDecoder::new(&data[..]);
+        let mut data = Vec::new();
+        z.read_to_end(&mut data)
+            .map_err(|e| corrupt(e.to_string()))?;
+
+        Ok(data)
+    }
+
+    /// Check that the loose object stored as `hash` inflates and hashes to
+    /// `hash`, deleting it otherwise, e.g. after a download whose content
+    /// doesn't match the hash it was saved under.
+    pub fn verify_loose_object(&self, hash: &str) -> error::Result<()> {
+        let hash = hash.trim();
+
+        let Some(object_path) = self.loose_object_path(hash) else {
+            return Err(Error::InvalidObjectName(hash.to_string()));
+        };
+
+        let mut data = Vec::new();
+        let inflated = ZlibDecoder::new(&std::fs::read(&object_path)?[..]).read_to_end(&mut data);
+
+        let reason = match inflated {
+            Err(e) => e.to_string(),
+            Ok(_) if hash_object_data(&data) != hash => {
+                format!("content hashes to {}", hash_object_data(&data))
+            }
+            Ok(_) => return Ok(()),
+        };
+
+        std::fs::remove_file(object_path)?;
+
+        Err(Error::CorruptObject {
+            hash: hash.to_string(),
+            reason,
+        })
+    }
+
+    /// Compress and write an object given in its loose form, returning its
+    /// hash.
+    pub fn write_object(&self, data: &[u8]) -> error::Result<String> {
+        let hash = hash_object_data(data);
+        let (prefix, rest) = hash.split_at(2);
+        let object_dir = self.objects_dir().join(prefix);
+
+        std::fs::create_dir_all(&object_dir)?;
+
+        let mut z = ZlibEncoder::new(Vec::new(), Compression::default());
+        z.write_all(data)?;
+
+        std::fs::write(object_dir.join(rest), z.finish()?)?;
+
+        Ok(hash)
+    }
+
+    fn packs(&self) -> Result<Arc<Vec<Pack>>> {
+        let cached = self
+            .packs
+            .read()
+            .map_err(|_| anyhow!("Pack cache lock poisoned"))?
+            .clone();
+
+        if let Some(packs) = cached {
+            return Ok(packs);
+        }
+
+        let packs = Arc::new(find_packs(&self.git_dir)?);
+
+        *self
+            .packs
+            .write()
+            .map_err(|_| anyhow!("Pack cache lock poisoned"))? = Some(packs.clone());
+
+        Ok(packs)
+    }
+
+    /// Forget the loaded packs, so that the next lookup sees the packs
+    /// written to `objects/pack` since.
+    pub fn refresh_packs(&self) {
+        if let Ok(mut packs) = self.packs.write() {
+            *packs = None;
+        }
+    }
+
+    /// Whether the object exists, loose or packed.
+    pub fn has_object(&self, hash: &str) -> bool {
+        match self.loose_object_path(hash) {
+            Some(path) => {
+                path.exists()
+                    || self
+                        .packs()
+                        .is_ok_and(|packs| has_packed_object(&packs, hash.trim()))
+            }
+            None => false,
+        }
+    }
+
+    /// All objects whose hash starts with the given (abbreviated) prefix.
+    pub fn find_objects_by_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        let prefix = prefix.trim().to_lowercase();
+
+        if prefix.len() < 4 || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok(vec![]);
+        }
+
+        let (dir, rest) = prefix.split_at(2);
+        let dir_path = self.objects_dir().join(dir);
+
+        let mut result = Vec::new();
+
+        if dir_path.is_dir() {
+            for entry in std::fs::read_dir(dir_path)? {
+                let name = entry?.file_name().to_string_lossy().to_string();
+
+                if name.starts_with(rest) {
+                    result.push(format!("{}{}", dir, name));
+                }
+            }
+        }
+
+        for pack in self.packs()?.iter() {
+            for hash in pack.hashes() {
+                if hash.starts_with(&prefix) && !result.contains(&hash) {
+                    result.push(hash);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// The hashes of all loose objects.
+    pub fn loose_objects(&self) -> Result<Vec<String>> {
+        let mut hashes = Vec::new();
+
+        for entry in WalkDir::new(self.objects_dir())
+            .min_depth(2)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            let Some(dir) = entry.path().parent().and_then(|dir| dir.file_name()) else {
+                continue;
+            };
+
+            let hash = format!(
+                "{}{}",
+                dir.to_string_lossy(),
+                entry.file_name().to_string_lossy()
+            );
+
+            if entry.file_type().is_file() && hash.len() == 40 {
+                hashes.push(hash);
+            }
+        }
+
+        Ok(hashes)
+    }
+
+    /// The raw content of a ref (a hash or `ref: <name>`), loose or packed.
+    pub fn read_ref(&self, name: &str) -> Result<Option<String>> {
+        let path = self.git_dir.join(name.trim());
+
+        if path.is_file() {
+            return Ok(Some(std::fs::read_to_string(path)?.trim().to_string()));
+        }
+
+        Ok(read_packed_refs(&self.git_dir)?
+            .into_iter()
+            .find(|(packed_name, _)| packed_name == name.trim())
+            .map(|(_, hash)| hash))
+    }
+
+    /// Follow a ref through symbolic refs to an object hash, empty when it
+    /// doesn't exist (e.g. the branch of an unborn `HEAD`).
+    pub fn resolve_ref(&self, name: &str) -> Result<String> {
+        let mut name = name.trim().to_string();
+
+        // `HEAD` -> branch -> hash, with some room for nested symbolic refs
+        for _ in 0..5 {
+            let Some(content) = self.read_ref(&name)? else {
+                return Ok(String::new());
+            };
+
+            let content = Ref::parse_ref_content(&content);
+
+            if !content.starts_with("refs/") {
+                return Ok(content);
+            }
+
+            name = content;
+        }
+
+        bail!("Too many levels of symbolic refs: {}", name)
+    }
+
+    /// What `HEAD` points at: a ref name, or a hash when detached.
+    pub fn head(&self) -> Result<String> {
+        let content = self
+            .read_ref("HEAD")?
+            .ok_or_else(|| anyhow!("{} has no HEAD", self.git_dir.display()))?;
+
+        Ok(Ref::parse_ref_content(&content))
+    }
+
+    pub fn update_ref(&self, name: &str, hash: &str) -> Result<()> {
+        let name = name.trim();
+
+        validate_ref_name(name)?;
+
+        let path = self.git_dir.join(name);
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, format!("{}\n", hash.trim()))?;
+
+        Ok(())
+    }
+
+    /// Update a ref and record the change in its reflog, and in the one of
+    /// `HEAD` when it is the current branch.
+    pub fn update_ref_logged(&self, name: &str, hash: &str, message: &str) -> Result<()> {
+        let name = name.trim();
+
+        validate_ref_name(name)?;
+
+        let old = self.resolve_ref(name)?;
+
+        self.update_ref(name, hash)?;
+
+        append_reflog(self, name, &old, hash, message)?;
+
+        if name != "HEAD" && self.head().ok().as_deref() == Some(name) {
+            append_reflog(self, "HEAD", &old, hash, message)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove a ref, loose or packed, and its reflog. Whether it existed.
+    pub fn delete_ref(&self, name: &str) -> Result<bool> {
+        let name = name.trim();
+
+        validate_ref_name(name)?;
+        let path = self.git_dir.join(name);
+        let mut deleted = false;
+
+        let log_path = self.git_dir.join("logs").join(name);
+
+        if log_path.is_file() {
+            std::fs::remove_file(log_path)?;
+        }
+
+        if path.is_file() {
+            std::fs::remove_file(path)?;
+            deleted = true;
+        }
+
+        let packed_path = self.git_dir.join("packed-refs");
+
+        if read_packed_refs(&self.git_dir)?
+            .iter()
+            .any(|(packed_name, _)| packed_name == name)
+        {
+            let mut lines = Vec::new();
+            let mut skip_peeled = false;
+
+            for line in std::fs::read_to_string(&packed_path)?.lines() {
+                // the peeled value follows the tag it belongs to
+                if line.starts_with('^') && skip_peeled {
+                    continue;
+                }
+
+                skip_peeled = line
+                    .split_once(' ')
+                    .is_some_and(|(_, packed_name)| packed_name.trim() == name);
+
+                if !skip_peeled {
+                    lines.push(format!("{}\n", line));
+                }
+            }
+
+            std::fs::write(packed_path, lines.concat())?;
+            deleted = true;
+        }
+
+        Ok(deleted)
+    }
+
+    /// Every ref under `refs/` with its raw content, loose refs taking
+    /// precedence over packed ones.
+    pub fn refs(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut result = Vec::new();
+
+        for entry in WalkDir::new(self.git_dir.join("refs"))
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let Ok(relative) = entry.path().strip_prefix(&self.git_dir) else {
+                continue;
+            };
+
+            let name = relative.to_string_lossy().replace('\\', "/");
+
+            result.push((name, std::fs::read(entry.path())?));
+        }
+
+        for (name, hash) in read_packed_refs(&self.git_dir)? {
+            if !result.iter().any(|(ref_name, _)| *ref_name == name) {
+                result.push((name, hash.into_bytes()));
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn shallow_path(&self) -> PathBuf {
+        self.git_dir.join("shallow")
+    }
+
+    /// Whether history was cut off by a shallow clone.
+    pub fn is_shallow(&self) -> bool {
+        self.shallow_path().is_file()
+    }
+
+    /// The commits listed in `shallow`, whose parents are missing on purpose.
+    pub fn shallow_commits(&self) -> Result<BTreeSet<String>> {
+        if !self.is_shallow() {
+            return Ok(BTreeSet::new());
+        }
+
+        Ok(std::fs::read_to_string(self.shallow_path())?
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+
+    /// Rewrite `shallow`, removing it when no commit is left.
+    pub fn set_shallow_commits(&self, commits: &BTreeSet<String>) -> Result<()> {
+        if commits.is_empty() {
+            if self.is_shallow() {
+                std::fs::remove_file(self.shallow_path())?;
+            }
+
+            return Ok(());
+        }
+
+        let content = commits
+            .iter()
+            .map(|commit| format!("{}\n", commit))
+            .collect::<String>();
+
+        std::fs::write(self.shallow_path(), content)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fresh directory per test, so tests can run in parallel
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dgit-test-{}-{}", name, std::process::id()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn test_repository_objects() {
+        let dir = temp_dir("objects");
+        let (repository, exists) = Repository::init(&dir, "main").unwrap();
+
+        assert!(!exists);
+        assert!(!repository.is_bare());
+
+        let hash = repository.write_object(b"blob 5\0hello").unwrap();
+
+        assert_eq!(hash, "b6fc4c620b67d95f953a5c1c1230aaab5db5a1b0");
+        assert!(repository.has_object(&hash));
+        assert_eq!(repository.read_object(&hash).unwrap(), b"blob 5\0hello");
+        assert_eq!(
+            repository.find_objects_by_prefix("b6fc4c").unwrap(),
+            vec![hash.clone()]
+        );
+        assert_eq!(repository.loose_objects().unwrap(), vec![hash.clone()]);
+        assert!(repository.verify_loose_object(&hash).is_ok());
+
+        // another object stored under the hash of the first one
+        let other = repository.write_object(b"blob 5\0world").unwrap();
+        let path = |hash: &str| repository.objects_dir().join(&hash[..2]).join(&hash[2..]);
+        std::fs::copy(path(&other), path(&hash)).unwrap();
+
+        assert!(matches!(
+            repository.verify_loose_object(&hash),
+            Err(Error::CorruptObject { .. })
+        ));
+        assert!(!repository.has_object(&hash));
+
+        // names that aren't 40 lowercase hex digits never reach the filesystem
+        let outside = dir.join("outside");
+        std::fs::write(&outside, "keep").unwrap();
+
+        for name in [
+            "ab/home/u/.bashrc".to_string(),
+            format!("ab{}", outside.display()),
+            "../../../../../../../../../../../outside".to_string(),
+            "é".repeat(20),
+            other.to_uppercase(),
+        ] {
+            assert!(repository.loose_object_path(&name).is_none());
+            assert!(!repository.has_object(&name));
+            assert!(matches!(
+                repository.verify_loose_object(&name),
+                Err(Error::InvalidObjectName(_))
+            ));
+        }
+
+        assert!(outside.is_file());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_repository_refs() {
+        let dir = temp_dir("refs");
+        let (repository, _) = Repository::init(&dir, "trunk").unwrap();
+
+        let hash = "b6fc4c620b67d95f953a5c1c1230aaab5db5a1b0";
+
+        assert_eq!(repository.head().unwrap(), "refs/heads/trunk");
+        assert_eq!(repository.resolve_ref("HEAD").unwrap(), "");
+
+        repository.update_ref("refs/heads/trunk", hash).unwrap();
+        std::fs::write(
+            repository.git_dir().join("packed-refs"),
+            format!("# pack-refs with: peeled\n{} refs/tags/v1\n", hash),
+        )
+        .unwrap();
+
+        assert_eq!(repository.resolve_ref("HEAD").unwrap(), hash);
+        assert_eq!(repository.resolve_ref("refs/tags/v1").unwrap(), hash);
+        assert_eq!(repository.refs().unwrap().len(), 2);
+
+        assert!(repository.delete_ref("refs/tags/v1").unwrap());
+        assert!(!repository.delete_ref("refs/tags/v1").unwrap());
+        assert_eq!(repository.resolve_ref("refs/tags/v1").unwrap(), "");
+        assert_eq!(repository.refs().unwrap().len(), 1);
+
+        for name in ["../outside", "/tmp/outside", "refs/heads/a..b", ""] {
+            assert!(repository.update_ref(name, hash).is_err());
+            assert!(repository.update_ref_logged(name, hash, "test").is_err());
+        }
+        assert!(!dir.join("outside").exists());
+
+        let subdir = dir.join("a/b");
+        std::fs::create_dir_all(&subdir).unwrap();
+
+        let discovered = Repository::discover(&subdir).unwrap();
+
+        assert_eq!(
+            discovered.git_dir(),
+            std::fs::canonicalize(repository.git_dir()).unwrap()
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_repository_shallow() {
+        let dir = temp_dir("shallow");
+        let repository = Repository::init_bare(&dir, "main").unwrap();
+
+        assert!(!repository.is_shallow());
+
+        let commits = BTreeSet::from(["b6fc4c620b67d95f953a5c1c1230aaab5db5a1b0".to_string()]);
+        repository.set_shallow_commits(&commits).unwrap();
+
+        assert!(repository.is_shallow());
+        assert_eq!(repository.shallow_commits().unwrap(), commits);
+
+        repository.set_shallow_commits(&BTreeSet::new()).unwrap();
+
+        assert!(!repository.is_shallow());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+use crate::git::git_fs::{find_objects_by_prefix, get_commit_object, get_object_header};
+use crate::git::head::resolve_head;
+use crate::git::objects::header::ObjectType;
+use crate::git::reflog::{read_reflog, reflog_value};
+use crate::git::refs::Ref;
+use crate::git::repository::Repository;
+use crate::git::walk;
+use anyhow::{anyhow, bail, Result};
+
+// the lookup order git uses for a short ref name
+fn ref_candidates(name: &str) -> Vec<String> {
+    vec![
+        name.to_string(),
+        format!("refs/{}", name),
+        format!("refs/tags/{}", name),
+        format!("refs/heads/{}", name),
+        format!("refs/remotes/{}", name),
+        format!("refs/remotes/{}/HEAD", name),
+    ]
+}
+
+pub fn is_full_hash(rev: &str) -> bool {
+    rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// The full name of the ref a (possibly short) ref name refers to, loose
+/// or packed.
+pub fn find_ref(name: &str) -> Result<Option<String>> {
+    let repository = Repository::current()?;
+
+    for candidate in ref_candidates(name.trim()) {
+        if repository.read_ref(&candidate)?.is_some() {
+            return Ok(Some(candidate));
+        }
+    }
+
+    Ok(None)
+}
+
+/// The ref a `<ref>@{n}` revision reads the reflog of: the current branch
+/// for a bare `@{n}`.
+pub fn reflog_ref(name: &str) -> Result<String> {
+    let repository = Repository::current()?;
+
+    match name {
+        "" => repository.head().map(|head| {
+            // a detached HEAD has no branch to read
+            if head.starts_with("refs/") {
+                head
+            } else {
+                "HEAD".to_string()
+            }
+        }),
+        "HEAD" | "@" => Ok("HEAD".to_string()),
+        name => find_ref(name)?.ok_or_else(|| anyhow!("Unknown ref: {}", name)),
+    }
+}
+
+// `<ref>@{n}`: the value of the ref `n` changes ago
+fn resolve_reflog_revision(name: &str, n: &str) -> Result<String> {
+    let Ok(n) = n.parse::<usize>() else {
+        bail!("Only @{{<n>}} reflog entries are supported, not @{{{}}}", n);
+    };
+
+    let name = reflog_ref(name)?;
+
+    reflog_value(&read_reflog(&Repository::current()?, &name)?, &name, n)
+}
+
+/// Resolve a revision (full or abbreviated hash, `HEAD`, branch, tag,
+/// any other ref name or `<ref>@{n}`) to an object hash.
+pub async fn resolve_revision(rev: &str) -> Result<String> {
+    let rev = rev.trim();
+
+    if let Some((name, n)) = rev
+        .strip_suffix('}')
+        .and_then(|rest| rest.rsplit_once("@{"))
+    {
+        return resolve_reflog_revision(name, n);
+    }
+
+    if is_full_hash(rev) {
+        return Ok(rev.to_lowercase());
+    }
+
+    if rev == "HEAD" || rev == "@" {
+        return resolve_head().await;
+    }
+
+    if let Some(name) = find_ref(rev)? {
+        return Ref::new(&name).resolve().await;
+    }
+
+    let matches = find_objects_by_prefix(rev)?;
+
+    match matches.len() {
+        0 => bail!("Not a valid object name: {}", rev),
+        1 => Ok(matches[0].clone()),
+        _ => bail!("Short object ID {} is ambiguous", rev),
+    }
+}
+
+/// Resolve a commit or tree hash to the hash of its tree.
+pub fn peel_to_tree(hash: &str) -> Result<String> {
+    match get_object_header(hash)?.object_type {
+        ObjectType::Tree => Ok(hash.to_string()),
+        ObjectType::Commit => Ok(get_commit_object(hash)?.content.tree_sha),
+        object_type => bail!("Object {} is a {}, not a tree-ish", hash, object_type),
+    }
+}
+
+/// Whether `ancestor` is reachable from `descendant` through commit parents.
+pub fn is_ancestor(ancestor: &str, descendant: &str) -> Result<bool> {
+    walk::is_ancestor(&Repository::current()?, ancestor, descendant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_full_hash() {
+        assert!(is_full_hash("0123456789abcdef0123456789abcdef01234567"));
+        assert!(!is_full_hash("0123456789abcdef"));
+        assert!(!is_full_hash("z123456789abcdef0123456789abcdef01234567"));
+    }
+}
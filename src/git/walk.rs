@@ -0,0 +1,94 @@
+use crate::git::objects::blob::BlobObject;
+use crate::git::objects::commit::CommitObject;
+use crate::git::objects::header::ObjectType;
+use crate::git::objects::tree::TreeObject;
+use crate::git::repository::Repository;
+use anyhow::Result;
+use std::collections::HashSet;
+
+/// The objects an object points to: tree and parents of a commit, entries
+/// of a tree, the tagged object of a tag. Submodule entries are skipped.
+pub fn object_children(repository: &Repository, hash: &str) -> Result<Vec<String>> {
+    parse_children(repository.read_object(hash)?)
+}
+
+/// [`object_children`] of an object given in its loose form.
+pub fn parse_children(data: Vec<u8>) -> Result<Vec<String>> {
+    let object = BlobObject::try_from(data.clone())?;
+
+    match object.header.object_type {
+        ObjectType::Blob => Ok(vec![]),
+        ObjectType::Commit => {
+            let commit = CommitObject::try_from(data)?;
+
+            let mut children = vec![commit.content.tree_sha];
+            children.extend(commit.content.parent_sha);
+
+            Ok(children)
+        }
+        ObjectType::Tree => Ok(TreeObject::try_from(data)?
+            .entries
+            .into_iter()
+            .filter(|entry| entry.object_type() != ObjectType::Commit)
+            .map(|entry| entry.hash)
+            .collect()),
+        ObjectType::Tag => Ok(String::from_utf8_lossy(&object.data)
+            .lines()
+            .find_map(|line| line.strip_prefix("object "))
+            .map(|object| vec![object.trim().to_string()])
+            .unwrap_or_default()),
+    }
+}
+
+/// Every object reachable from `tips` that is present in the repository,
+/// without walking past the objects in `exclude`.
+pub fn reachable_objects(
+    repository: &Repository,
+    tips: &[String],
+    exclude: &HashSet<String>,
+) -> Result<Vec<String>> {
+    let mut result = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue = tips.to_vec();
+
+    while let Some(hash) = queue.pop() {
+        if exclude.contains(&hash) || !seen.insert(hash.clone()) {
+            continue;
+        }
+
+        if !repository.has_object(&hash) {
+            continue;
+        }
+
+        queue.extend(object_children(repository, &hash)?);
+
+        result.push(hash);
+    }
+
+    Ok(result)
+}
+
+/// Whether `ancestor` is reachable from `descendant` through the commit
+/// parents present in `repository`.
+pub fn is_ancestor(repository: &Repository, ancestor: &str, descendant: &str) -> Result<bool> {
+    let mut queue = vec![descendant.to_string()];
+    let mut seen = HashSet::new();
+
+    while let Some(hash) = queue.pop() {
+        if hash == ancestor {
+            return Ok(true);
+        }
+
+        if !seen.insert(hash.clone()) || !repository.has_object(&hash) {
+            continue;
+        }
+
+        queue.extend(
+            CommitObject::try_from(repository.read_object(&hash)?)?
+                .content
+                .parent_sha,
+        );
+    }
+
+    Ok(false)
+}
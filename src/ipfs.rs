@@ -1,29 +1,100 @@
-use crate::config::Config;
-use anyhow::{bail, Result};
+use crate::config::{Config, StorageBackend};
+use crate::error::{Error, Result};
 use pinata_sdk::{PinByFile, PinataApi};
+use reqwest::header::CONTENT_LENGTH;
+use reqwest::multipart::{Form, Part};
 use reqwest::Client;
 use std::path::Path;
 use tokio::fs::{create_dir_all, File};
 use tokio::io::AsyncWriteExt;
 
+fn storage_error(error: impl std::fmt::Display) -> Error {
+    Error::Storage(error.to_string())
+}
+
 pub async fn load_to_ipfs(file_path: &str) -> Result<String> {
-    println!("Loading to IPFS...");
+    load_to_storage(Config::storage_backend()?, file_path).await
+}
 
+pub async fn load_to_storage(backend: StorageBackend, file_path: &str) -> Result<String> {
+    match backend {
+        StorageBackend::Pinata => load_to_pinata(file_path).await,
+        StorageBackend::Ipfs => load_to_ipfs_node(file_path).await,
+    }
+}
+
+async fn load_to_pinata(file_path: &str) -> Result<String> {
     let api = PinataApi::new(
-        Config::get_pinata_api_key(),
-        Config::get_pinata_secret_api_key(),
+        Config::get_pinata_api_key()?,
+        Config::get_pinata_secret_api_key()?,
     )
-    .unwrap();
+    .map_err(|e| Error::Storage(format!("Failed to create Pinata client: {:?}", e)))?;
 
     let result = api.pin_file(PinByFile::new(file_path)).await;
 
-    if let Ok(pinned_object) = result {
-        println!("IPFS hash: {}", pinned_object.ipfs_hash);
+    match result {
+        Ok(pinned_object) => Ok(pinned_object.ipfs_hash),
+        Err(e) => Err(Error::Storage(format!("Failed to pin object: {:?}", e))),
+    }
+}
+
+// POST /api/v0/add of a Kubo node
+async fn load_to_ipfs_node(file_path: &str) -> Result<String> {
+    let url = format!(
+        "{}/api/v0/add?pin=true",
+        Config::ipfs_api_url()?.trim_end_matches('/')
+    );
+
+    let content = tokio::fs::read(file_path).await?;
+    let form = Form::new().part("file", Part::bytes(content).file_name("object"));
+
+    let response = Client::new()
+        .post(&url)
+        .multipart(form)
+        .send()
+        .await
+        .map_err(storage_error)?;
 
-        Ok(pinned_object.ipfs_hash)
-    } else {
-        bail!("Failed to pin object: {:?}", result.unwrap_err());
+    if !response.status().is_success() {
+        return Err(Error::Storage(format!(
+            "Failed to add object to IPFS: {:?}",
+            response.status()
+        )));
     }
+
+    let body = response.text().await.map_err(storage_error)?;
+    let body: serde_json::Value = serde_json::from_str(&body).map_err(storage_error)?;
+
+    body["Hash"]
+        .as_str()
+        .map(|hash| hash.to_string())
+        .ok_or_else(|| Error::Storage(format!("Unexpected response from IPFS node: {}", body)))
+}
+
+/// The size of a stored object, from the first gateway that answers a
+/// `HEAD` request with a `Content-Length`.
+pub async fn object_size(ipfs_hash: &str) -> Result<Option<u64>> {
+    let client = Client::new();
+
+    for gateway in Config::gateways()? {
+        let url = format!("{}{}", gateway, ipfs_hash);
+
+        let Ok(response) = client.head(&url).send().await else {
+            continue;
+        };
+
+        let size = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+
+        if response.status().is_success() && size.is_some() {
+            return Ok(size);
+        }
+    }
+
+    Ok(None)
 }
 
 pub async fn download_from_ipfs(ipfs_hash: &str, file_path: &str) -> Result<()> {
@@ -31,23 +102,36 @@ pub async fn download_from_ipfs(ipfs_hash: &str, file_path: &str) -> Result<()>
         create_dir_all(parent).await?;
     }
 
-    let url = format!("{}{}", Config::ipfs_prefix(), ipfs_hash);
+    let client = Client::new();
 
-    println!("Downloading from IPFS: {}", url);
+    let mut last_error = None;
 
-    let client = Client::new();
-    let response = client.get(&url).send().await?;
+    for gateway in Config::gateways()? {
+        let url = format!("{}{}", gateway, ipfs_hash);
+
+        let response = match client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                last_error = Some(Error::Storage(format!(
+                    "Failed to download from IPFS: {:?}",
+                    response.status()
+                )));
+                continue;
+            }
+            Err(e) => {
+                last_error = Some(storage_error(e));
+                continue;
+            }
+        };
 
-    if response.status().is_success() {
         let mut dest = File::create(file_path).await?;
-        let content = response.bytes().await?;
+        let content = response.bytes().await.map_err(storage_error)?;
 
         dest.write_all(&content).await?;
 
-        println!("OK");
-
-        Ok(())
-    } else {
-        bail!("Failed to download from IPFS: {:?}", response.status());
+        return Ok(());
     }
+
+    Err(last_error
+        .unwrap_or_else(|| Error::Storage(format!("Failed to download from IPFS: {}", ipfs_hash))))
 }
@@ -0,0 +1,128 @@
+use crate::git::repository::Repository;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// The objects a push uploaded to storage but hasn't recorded on the
+/// contract yet, so an interrupted push resumes without uploading them
+/// again. Kept per contract in `.git/dgit/uploads/<address>`, one
+/// `<object> <cid>` line per upload.
+pub struct PushJournal {
+    path: PathBuf,
+    uploads: BTreeMap<String, String>,
+}
+
+impl PushJournal {
+    pub fn open(repository: &Repository, contract_address: &str) -> Result<Self> {
+        let path = repository
+            .git_dir()
+            .join("dgit")
+            .join("uploads")
+            .join(contract_address.to_lowercase());
+
+        let mut uploads = BTreeMap::new();
+
+        if path.is_file() {
+            for line in std::fs::read_to_string(&path)?.lines() {
+                // a line cut short by an interruption is skipped
+                if let Some((hash, cid)) = line.split_once(' ') {
+                    if !hash.is_empty() && !cid.is_empty() {
+                        uploads.insert(hash.to_string(), cid.to_string());
+                    }
+                }
+            }
+        }
+
+        Ok(PushJournal { path, uploads })
+    }
+
+    pub fn uploads(&self) -> &BTreeMap<String, String> {
+        &self.uploads
+    }
+
+    /// The CID `hash` was uploaded as by an earlier push.
+    pub fn get(&self, hash: &str) -> Option<&String> {
+        self.uploads.get(hash)
+    }
+
+    /// Record an upload as soon as it's done, appending to the journal.
+    pub fn record(&mut self, hash: &str, cid: &str) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        writeln!(file, "{} {}", hash, cid)?;
+        file.sync_data()?;
+
+        self.uploads.insert(hash.to_string(), cid.to_string());
+
+        Ok(())
+    }
+
+    /// Forget uploads that are recorded on the contract.
+    pub fn remove(&mut self, hashes: &[String]) -> Result<()> {
+        let before = self.uploads.len();
+
+        for hash in hashes {
+            self.uploads.remove(hash);
+        }
+
+        if self.uploads.len() == before {
+            return Ok(());
+        }
+
+        if self.uploads.is_empty() {
+            if self.path.exists() {
+                std::fs::remove_file(&self.path)?;
+            }
+
+            return Ok(());
+        }
+
+        let lines = self
+            .uploads
+            .iter()
+            .map(|(hash, cid)| format!("{} {}\n", hash, cid))
+            .collect::<String>();
+
+        std::fs::write(&self.path, lines)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_journal() {
+        let dir = std::env::temp_dir().join(format!("dgit-journal-{}", std::process::id()));
+        let repository = Repository::init_bare(&dir, "main").unwrap();
+
+        let mut journal = PushJournal::open(&repository, "0xABC").unwrap();
+        journal.record("aaaa", "QmA").unwrap();
+        journal.record("bbbb", "QmB").unwrap();
+
+        let mut journal = PushJournal::open(&repository, "0xabc").unwrap();
+        assert_eq!(journal.get("aaaa"), Some(&"QmA".to_string()));
+        assert_eq!(journal.uploads().len(), 2);
+
+        journal.remove(&["aaaa".to_string()]).unwrap();
+
+        let mut journal = PushJournal::open(&repository, "0xabc").unwrap();
+        assert_eq!(journal.get("aaaa"), None);
+        assert_eq!(journal.get("bbbb"), Some(&"QmB".to_string()));
+
+        journal.remove(&["bbbb".to_string()]).unwrap();
+        assert!(!dir.join("dgit").join("uploads").join("0xabc").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,6 +2,14 @@ pub mod cli;
 pub mod commands;
 pub mod config;
 pub mod contract_interaction;
+pub mod error;
 pub mod git;
 pub mod ipfs;
+pub mod journal;
+pub mod promisor;
+pub mod remote;
+pub mod remote_helper;
+pub mod signature;
+pub mod signer;
+pub mod transaction;
 pub mod utils;
@@ -1,43 +1,364 @@
 use anyhow::bail;
 use clap::Parser;
+use colored::Colorize;
+use dgit2::cli::AccessCommands;
+use dgit2::cli::AccountCommands;
+use dgit2::cli::BundleCommands;
 use dgit2::cli::Cli;
 use dgit2::cli::Commands;
+use dgit2::cli::RemoteCommands;
 use dgit2::commands;
-use dgit2::commands::{cat_file, write_tree};
+use dgit2::commands::{
+    cat_file, write_tree, AccessAction, AccountAction, AccountSource, CatFileMode, ConfigAction,
+    ProtectAction, RemoteAction,
+};
+use dgit2::contract_interaction::Role;
+use dgit2::error::{exit_code, is_silent};
+use dgit2::git::config::ConfigScope;
+use dgit2::git::filter::ObjectFilter;
 use dgit2::ipfs::{download_from_ipfs, load_to_ipfs};
+use std::str::FromStr;
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() {
     let parser = Cli::parse();
 
     dotenv::dotenv().ok();
 
-    match parser.command {
+    if let Err(e) = run(parser.command).await {
+        if !is_silent(&e) {
+            eprintln!("{} {:#}", "error:".red(), e);
+        }
+
+        std::process::exit(exit_code(&e));
+    }
+}
+
+async fn run(command: Commands) -> anyhow::Result<()> {
+    match command {
         Commands::Init {
             contract_address,
             deploy,
+            initial_branch,
         } => {
             if deploy {
+                commands::init(None, initial_branch).await?;
                 commands::deploy_repo_contract().await.map(|_| ())
             } else {
-                commands::init(contract_address.unwrap()).await
+                commands::init(contract_address, initial_branch).await
             }
         }
-        Commands::Clone { contract_address } => commands::clone(contract_address).await,
-        Commands::Pull => commands::pull().await,
-        Commands::Push => commands::push().await,
+        Commands::Clone {
+            contract_address,
+            dir,
+            bare,
+            branch,
+            no_checkout,
+            depth,
+            single_branch,
+            filter,
+        } => {
+            let options = commands::CloneOptions {
+                bare,
+                branch,
+                no_checkout,
+                depth,
+                single_branch,
+                filter: filter
+                    .map(|filter| ObjectFilter::from_str(&filter))
+                    .transpose()?,
+            };
+
+            commands::clone(contract_address, dir, &options).await
+        }
+        Commands::Import {
+            path,
+            contract_address,
+            no_verify,
+        } => commands::import(&path, contract_address, !no_verify)
+            .await
+            .map(|_| ()),
+        Commands::Export {
+            dir,
+            bare,
+            contract_address,
+        } => commands::export(&dir, bare, contract_address).await,
+        Commands::Bundle {
+            command: BundleCommands::Create { file, refs },
+        } => commands::bundle_create(&file, refs).await,
+        Commands::Pull { remote, refspecs } => commands::pull(remote, refspecs).await,
+        Commands::Push {
+            remote,
+            refspecs,
+            dry_run,
+            status,
+            delete,
+        } => {
+            let options = commands::PushOptions {
+                dry_run,
+                status,
+                delete,
+            };
+
+            commands::push(remote, refspecs, &options).await
+        }
+        Commands::Fetch {
+            remote,
+            refspecs,
+            deepen,
+            unshallow,
+            prune,
+        } => {
+            let options = commands::FetchOptions {
+                deepen,
+                unshallow,
+                prune,
+            };
+
+            commands::fetch(remote, refspecs, &options).await
+        }
+        Commands::Remote { verbose, command } => {
+            let action = match command {
+                None | Some(RemoteCommands::List) => RemoteAction::List { verbose },
+                Some(RemoteCommands::Add {
+                    name,
+                    contract_address,
+                    rpc_url,
+                    storage,
+                }) => RemoteAction::Add {
+                    name,
+                    contract_address,
+                    rpc_url,
+                    storage,
+                },
+                Some(RemoteCommands::Remove { name }) => RemoteAction::Remove { name },
+                Some(RemoteCommands::Rename { old, new }) => RemoteAction::Rename { old, new },
+            };
+
+            commands::remote(action)
+        }
+        Commands::Account { command } => {
+            let action = match command {
+                AccountCommands::List => AccountAction::List,
+                AccountCommands::New => AccountAction::New,
+                AccountCommands::Import {
+                    mnemonic,
+                    derivation_path,
+                    keystore,
+                } => {
+                    let source = match keystore {
+                        Some(file) => AccountSource::Keystore(file),
+                        None if mnemonic => AccountSource::Mnemonic { derivation_path },
+                        None => AccountSource::PrivateKey,
+                    };
+
+                    AccountAction::Import { source }
+                }
+            };
+
+            commands::account(action)
+        }
+        Commands::Access { remote, command } => {
+            let action = match command {
+                AccessCommands::List => AccessAction::List,
+                AccessCommands::Grant { address, role } => AccessAction::Grant {
+                    address,
+                    role: Role::from_str(&role)?,
+                },
+                AccessCommands::Revoke { address } => AccessAction::Revoke { address },
+            };
+
+            commands::access(remote, action).await
+        }
+        Commands::Protect {
+            name,
+            pushers,
+            allow_force,
+            allow_deletion,
+            remove,
+            remote,
+        } => {
+            let action = match name {
+                None => ProtectAction::List,
+                Some(name) if remove => ProtectAction::Remove { name },
+                Some(name) => ProtectAction::Add {
+                    name,
+                    pushers,
+                    allow_force,
+                    allow_deletion,
+                },
+            };
+
+            commands::protect(remote, action).await
+        }
         Commands::Status => commands::status().await,
         Commands::Deploy => commands::deploy_repo_contract().await.map(|_| ()),
         Commands::LoadFile { file_path } => {
-            load_to_ipfs(file_path.as_ref().unwrap()).await.map(|_| ())
+            let Some(file_path) = file_path else {
+                bail!("Missing --file-path argument");
+            };
+
+            println!("IPFS hash: {}", load_to_ipfs(&file_path).await?);
+
+            Ok(())
+        }
+        Commands::DownloadFile { chash, path } => Ok(download_from_ipfs(&chash, &path).await?),
+        Commands::CatFile {
+            show_type,
+            show_size,
+            exists,
+            pretty_print: _,
+            batch,
+            batch_check,
+            object,
+        } => {
+            let mode = if show_type {
+                CatFileMode::Type
+            } else if show_size {
+                CatFileMode::Size
+            } else if exists {
+                CatFileMode::Exists
+            } else if batch {
+                CatFileMode::Batch
+            } else if batch_check {
+                CatFileMode::BatchCheck
+            } else {
+                CatFileMode::Pretty
+            };
+
+            cat_file(mode, object.as_deref()).await
         }
-        Commands::DownloadFile { chash, path } => download_from_ipfs(&chash, &path).await,
-        Commands::CatFile { pretty_print } => cat_file(pretty_print.as_str()),
         Commands::HashObject { write } => commands::hash_object(write.as_str()),
-        Commands::LsTree { path, name_only } => commands::ls_tree(path.as_str(), name_only),
+        Commands::LsTree {
+            tree_ish,
+            paths,
+            recursive,
+            show_trees,
+            only_trees,
+            long,
+            name_only,
+        } => {
+            let options = commands::LsTreeOptions {
+                recursive,
+                show_trees,
+                only_trees,
+                long,
+                name_only,
+            };
+
+            commands::ls_tree(&tree_ish, &paths, &options).await
+        }
+        Commands::LsFiles {
+            cached,
+            others,
+            ignored,
+            stage,
+        } => {
+            let options = commands::LsFilesOptions {
+                cached,
+                others,
+                ignored,
+                stage,
+            };
+
+            commands::ls_files(&options).await
+        }
         Commands::WriteTree => write_tree("."),
         Commands::ContractAddress => commands::contract_address().await,
-        Commands::Commit { message } => commands::commit(message).await.map(|_| ()),
+        Commands::Commit {
+            message,
+            author,
+            date,
+            eth_identity,
+            sign,
+        } => {
+            let options = commands::CommitOptions {
+                author,
+                date,
+                eth_identity,
+                sign,
+            };
+
+            commands::commit(message, &options).await.map(|_| ())
+        }
+        Commands::Log {
+            revision,
+            max_count,
+            show_signature,
+        } => {
+            let options = commands::LogOptions {
+                max_count,
+                show_signature,
+            };
+
+            commands::log(revision, &options).await
+        }
+        Commands::Reflog {
+            name,
+            remote,
+            max_count,
+            pusher,
+            json,
+        } => {
+            let options = commands::ReflogOptions {
+                max_count,
+                pusher,
+                json,
+            };
+
+            match remote {
+                // a bare `--remote` reads the default remote
+                Some(remote) => {
+                    let remote = Some(remote).filter(|remote| !remote.is_empty());
+
+                    commands::remote_reflog(remote, name, &options).await
+                }
+                None => commands::reflog(name, &options).await,
+            }
+        }
+        Commands::VerifyCommit { commits } => commands::verify_commit(&commits).await,
+        Commands::Config {
+            global,
+            system,
+            local,
+            get,
+            set,
+            add,
+            unset,
+            list,
+            key,
+            value,
+        } => {
+            let scope = if global {
+                Some(ConfigScope::Global)
+            } else if system {
+                Some(ConfigScope::System)
+            } else if local {
+                Some(ConfigScope::Local)
+            } else {
+                None
+            };
+
+            let action = if let Some(key) = get {
+                ConfigAction::Get(key)
+            } else if let Some(mut set) = set {
+                ConfigAction::Set(set.remove(0), set.remove(0))
+            } else if let Some(mut add) = add {
+                ConfigAction::Add(add.remove(0), add.remove(0))
+            } else if let Some(key) = unset {
+                ConfigAction::Unset(key)
+            } else if list {
+                ConfigAction::List
+            } else {
+                match (key, value) {
+                    (Some(key), Some(value)) => ConfigAction::Set(key, value),
+                    (Some(key), None) => ConfigAction::Get(key),
+                    _ => bail!("Missing <key> argument"),
+                }
+            };
+
+            commands::config(scope, action)
+        }
         #[allow(unreachable_patterns)]
         _ => bail!("Not implemented yet"),
     }
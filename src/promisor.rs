@@ -0,0 +1,133 @@
+use crate::commands::download_object;
+use crate::contract_interaction::ContractInteraction;
+use crate::error::{self, Error};
+use crate::git::repository::Repository;
+use crate::remote::Remote;
+use anyhow::Result;
+use std::future::Future;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// The remote a partial clone left objects on, named by
+/// `extensions.partialClone`.
+pub fn promisor_remote(repository: &Repository) -> Result<Option<Remote>> {
+    let config = repository.config()?;
+
+    match config.get("extensions.partialClone") {
+        Some(name) => Remote::from_config(&config, &name).map(Some),
+        None => Ok(None),
+    }
+}
+
+// object reads are synchronous, but dgit runs them inside the runtime of its
+// `main`: the multi-threaded one lets a worker block, a current-thread one
+// can't, so the future gets a thread and a runtime of its own there
+fn block_on<F>(future: F) -> Result<F::Output>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::CurrentThread => {
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| Ok(tokio::runtime::Runtime::new()?.block_on(future)))
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+        }
+        Ok(handle) => Ok(tokio::task::block_in_place(|| handle.block_on(future))),
+        Err(_) => Ok(tokio::runtime::Runtime::new()?.block_on(future)),
+    }
+}
+
+/// Download an object a partial clone left out from the contract of its
+/// promisor remote. Returns false when the repository isn't a partial clone.
+pub fn fetch_promised_object(repository: &Repository, hash: &str) -> Result<bool> {
+    let Some(remote) = promisor_remote(repository)? else {
+        return Ok(false);
+    };
+
+    let contract = ContractInteraction::for_remote(&remote)?;
+
+    block_on(download_object(&contract, repository, hash))??;
+
+    Ok(true)
+}
+
+/// Read an object, fetching it from the promisor remote first when a
+/// partial clone left it out.
+pub fn read_object(repository: &Repository, hash: &str) -> error::Result<Vec<u8>> {
+    match repository.read_object(hash) {
+        Err(Error::ObjectNotFound(hash)) => match fetch_promised_object(repository, &hash) {
+            Ok(true) => repository.read_object(&hash),
+            Ok(false) => Err(Error::ObjectNotFound(hash)),
+            Err(e) => Err(e
+                .downcast::<Error>()
+                .unwrap_or_else(|e| Error::Storage(e.to_string()))),
+        },
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MISSING: &str = "b6fc4c620b67d95f953a5c1c1230aaab5db5a1b0";
+
+    #[test]
+    fn test_block_on() {
+        assert_eq!(block_on(async { 1 }).unwrap(), 1);
+
+        let current_thread = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        assert_eq!(
+            current_thread.block_on(async { block_on(async { 2 }).unwrap() }),
+            2
+        );
+
+        let multi_thread = tokio::runtime::Runtime::new().unwrap();
+        assert_eq!(
+            multi_thread.block_on(async { block_on(async { 3 }).unwrap() }),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_object_from_promisor() {
+        let dir = std::env::temp_dir().join(format!("dgit-test-promisor-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let (repository, _) = Repository::init(&dir, "main").unwrap();
+
+        assert!(matches!(
+            read_object(&repository, MISSING),
+            Err(Error::ObjectNotFound(_))
+        ));
+
+        // nothing listens on the discard port, so the fetch is attempted and
+        // fails instead of the object being reported missing
+        let mut config = std::fs::read_to_string(repository.config_path()).unwrap_or_default();
+        config.push_str(
+            "[extensions]\n\tpartialClone = origin\n[remote \"origin\"]\n\
+             \turl = dgit::0x5FbDB2315678afecb367f032d93F642f64180aa3\n\
+             \trpcUrl = http://127.0.0.1:9\n\tpromisor = true\n",
+        );
+        std::fs::write(repository.config_path(), config).unwrap();
+
+        assert_eq!(
+            promisor_remote(&repository)
+                .unwrap()
+                .unwrap()
+                .rpc_url
+                .as_deref(),
+            Some("http://127.0.0.1:9")
+        );
+        assert!(matches!(
+            read_object(&repository, MISSING),
+            Err(Error::Chain(_))
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,433 @@
+use crate::config::StorageBackend;
+use crate::git::config::{Config, ConfigKey, ConfigScope};
+use crate::git::filter::ObjectFilter;
+use crate::git::head::get_head;
+use crate::git::refspec::Refspec;
+use crate::git::repository::Repository;
+use anyhow::{anyhow, bail, Result};
+use std::str::FromStr;
+
+pub const DEFAULT_REMOTE: &str = "origin";
+
+/// A repository contract the local repository is synced with, configured
+/// in a `[remote "<name>"]` section.
+#[derive(Debug, Clone)]
+pub struct Remote {
+    pub name: String,
+    pub contract_address: String,
+    /// Overrides `dgit.rpcUrl` for this remote.
+    pub rpc_url: Option<String>,
+    /// Overrides `dgit.storage` for this remote.
+    pub storage: Option<StorageBackend>,
+    pub fetch: Vec<Refspec>,
+    pub push: Vec<Refspec>,
+    /// The `partialclonefilter` of a partial clone from this remote.
+    pub filter: Option<ObjectFilter>,
+}
+
+pub fn validate_contract_address(address: &str) -> Result<()> {
+    let re = regex::Regex::new(r"^0x[a-fA-F0-9]{40}$")?;
+
+    if !re.is_match(address) {
+        bail!("Invalid contract address: {}", address);
+    }
+
+    Ok(())
+}
+
+// `dgit::0x...`, `dgit://0x...` or a bare address
+fn parse_url(url: &str) -> String {
+    url.trim()
+        .trim_start_matches("dgit::")
+        .trim_start_matches("dgit://")
+        .to_string()
+}
+
+// move the tracking refs of the remote `old` to `new`: the loose ones with
+// their reflogs, the packed ones, and the symbolic refs pointing at them
+// like `refs/remotes/<old>/HEAD`
+fn rename_tracking_refs(repository: &Repository, old: &str, new: &str) -> Result<()> {
+    let git_dir = repository.git_dir();
+    let old_prefix = format!("refs/remotes/{}/", old);
+    let new_prefix = format!("refs/remotes/{}/", new);
+
+    for dir in ["refs/remotes", "logs/refs/remotes"] {
+        let tracking_refs = git_dir.join(dir).join(old);
+
+        if tracking_refs.is_dir() {
+            std::fs::rename(tracking_refs, git_dir.join(dir).join(new))?;
+        }
+    }
+
+    let renamed = |name: &str| match name.strip_prefix(&old_prefix) {
+        Some(rest) => format!("{}{}", new_prefix, rest),
+        None => name.to_string(),
+    };
+
+    for (name, content) in repository.refs()? {
+        let content = String::from_utf8_lossy(&content);
+
+        if let (true, Some(target)) = (
+            name.starts_with(&new_prefix),
+            content.trim().strip_prefix("ref: "),
+        ) {
+            std::fs::write(git_dir.join(&name), format!("ref: {}\n", renamed(target)))?;
+        }
+    }
+
+    let packed_path = git_dir.join("packed-refs");
+
+    if packed_path.is_file() {
+        let mut lines = Vec::new();
+
+        for line in std::fs::read_to_string(&packed_path)?.lines() {
+            match line.split_once(' ') {
+                Some((hash, name)) if !line.starts_with('#') => {
+                    lines.push(format!("{} {}\n", hash, renamed(name.trim())))
+                }
+                _ => lines.push(format!("{}\n", line)),
+            }
+        }
+
+        std::fs::write(packed_path, lines.concat())?;
+    }
+
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains(['/', '.', ' ', '"', '\\']) {
+        bail!("Invalid remote name: '{}'", name);
+    }
+
+    Ok(())
+}
+
+impl Remote {
+    pub fn url(&self) -> String {
+        format!("dgit::{}", self.contract_address)
+    }
+
+    pub fn get(name: &str) -> Result<Self> {
+        Self::from_config(&Config::load()?, name)
+    }
+
+    /// The remote `name` as configured in `config`, for a repository other
+    /// than the current one.
+    pub fn from_config(config: &Config, name: &str) -> Result<Self> {
+        let key = |field: &str| format!("remote.{}.{}", name, field);
+
+        let contract_address = match config.get(&key("url")) {
+            Some(url) => parse_url(&url),
+            // repositories created before named remotes had a single contract
+            None if name == DEFAULT_REMOTE => config
+                .get("dgit.contractAddress")
+                .or_else(|| {
+                    let git_dir = Repository::current().ok()?.git_dir().to_path_buf();
+
+                    std::fs::read_to_string(git_dir.join("DGITCONFIG"))
+                        .ok()
+                        .map(|content| content.trim().to_string())
+                })
+                .ok_or_else(|| {
+                    anyhow!(
+                        "No remote '{}' configured, add one with `dgit remote add`",
+                        name
+                    )
+                })?,
+            None => bail!("No such remote: '{}'", name),
+        };
+
+        let parse_refspecs = |field: &str| {
+            config
+                .get_all(&key(field))
+                .iter()
+                .map(|refspec| Refspec::from_str(refspec))
+                .collect::<Result<Vec<Refspec>>>()
+        };
+
+        let mut fetch = parse_refspecs("fetch")?;
+
+        if fetch.is_empty() {
+            fetch.push(Refspec::default_fetch(name));
+        }
+
+        let mut push = parse_refspecs("push")?;
+
+        if push.is_empty() {
+            push = Refspec::default_push();
+        }
+
+        Ok(Remote {
+            name: name.to_string(),
+            contract_address,
+            rpc_url: config.get(&key("rpcUrl")),
+            storage: config
+                .get(&key("storage"))
+                .map(|storage| StorageBackend::from_str(&storage))
+                .transpose()?,
+            fetch,
+            push,
+            filter: config
+                .get(&key("partialclonefilter"))
+                .map(|filter| ObjectFilter::from_str(&filter))
+                .transpose()?,
+        })
+    }
+
+    /// The remote git invokes a remote helper with: the configured remote
+    /// of that name when it points at `url`, otherwise a remote with the
+    /// default refspecs (e.g. `git clone dgit::0x...` before the config
+    /// is written, or a URL used directly on the command line).
+    pub fn from_url(name: &str, url: &str) -> Result<Self> {
+        let contract_address = parse_url(url);
+
+        validate_contract_address(&contract_address)?;
+
+        if let Ok(remote) = Remote::get(name) {
+            if remote
+                .contract_address
+                .eq_ignore_ascii_case(&contract_address)
+            {
+                return Ok(remote);
+            }
+        }
+
+        Ok(Remote {
+            name: name.to_string(),
+            contract_address,
+            rpc_url: None,
+            storage: None,
+            fetch: vec![Refspec::default_fetch(name)],
+            push: Refspec::default_push(),
+            filter: None,
+        })
+    }
+
+    pub fn names() -> Result<Vec<String>> {
+        let mut names = Vec::new();
+
+        for (_, key, _) in Config::load()?.entries() {
+            if let (true, Some(name)) = (key.section == "remote", key.subsection) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        Ok(names)
+    }
+
+    pub fn list() -> Result<Vec<Remote>> {
+        Self::names()?
+            .iter()
+            .map(|name| Remote::get(name))
+            .collect()
+    }
+
+    /// The remote of the current branch (`branch.<name>.remote`), or `origin`.
+    pub async fn default_name() -> Result<String> {
+        let head = get_head().await.unwrap_or_default();
+
+        let branch_remote = head.strip_prefix("refs/heads/").and_then(|branch| {
+            Config::load()
+                .ok()?
+                .get(&format!("branch.{}.remote", branch))
+        });
+
+        Ok(branch_remote.unwrap_or(DEFAULT_REMOTE.to_string()))
+    }
+
+    pub fn add(
+        name: &str,
+        contract_address: &str,
+        rpc_url: Option<&str>,
+        storage: Option<StorageBackend>,
+    ) -> Result<Remote> {
+        validate_name(name)?;
+        validate_contract_address(contract_address)?;
+
+        let mut file = Config::open_scope(ConfigScope::Local)?;
+
+        if !file
+            .get_all(&ConfigKey::from_str(&format!("remote.{}.url", name))?)
+            .is_empty()
+        {
+            bail!("Remote {} already exists", name);
+        }
+
+        let key = |field: &str| ConfigKey::from_str(&format!("remote.{}.{}", name, field));
+
+        file.set(&key("url")?, &format!("dgit::{}", contract_address));
+        file.set(&key("fetch")?, &Refspec::default_fetch(name).to_string());
+
+        if let Some(rpc_url) = rpc_url {
+            file.set(&key("rpcUrl")?, rpc_url);
+        }
+
+        if let Some(storage) = storage {
+            file.set(&key("storage")?, &storage.to_string());
+        }
+
+        file.save()?;
+
+        Remote::get(name)
+    }
+
+    pub fn remove(name: &str) -> Result<()> {
+        let mut file = Config::open_scope(ConfigScope::Local)?;
+
+        if !file.remove_section("remote", Some(name)) {
+            bail!("No such remote: '{}'", name);
+        }
+
+        // branches tracking the removed remote no longer track anything
+        for (key, value) in file.entries() {
+            if key.section == "branch" && key.name == "remote" && value == name {
+                file.unset(&key);
+                file.unset(&ConfigKey {
+                    name: "merge".to_string(),
+                    ..key
+                });
+            }
+        }
+
+        file.save()?;
+
+        let repository = Repository::current()?;
+        let prefix = format!("refs/remotes/{}/", name);
+
+        // loose and packed tracking refs with their reflogs
+        for (ref_name, _) in repository.refs()? {
+            if ref_name.starts_with(&prefix) {
+                repository.delete_ref(&ref_name)?;
+            }
+        }
+
+        for dir in ["refs/remotes", "logs/refs/remotes"] {
+            let tracking_refs = repository.git_dir().join(dir).join(name);
+
+            if tracking_refs.is_dir() {
+                std::fs::remove_dir_all(tracking_refs)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn rename(old: &str, new: &str) -> Result<()> {
+        validate_name(new)?;
+
+        let mut file = Config::open_scope(ConfigScope::Local)?;
+
+        if file.subsections("remote").iter().any(|name| name == new) {
+            bail!("Remote {} already exists", new);
+        }
+
+        if !file.rename_section("remote", Some(old), Some(new)) {
+            bail!("No such remote: '{}'", old);
+        }
+
+        // keep the default fetch refspec pointing at the renamed tracking refs
+        let fetch = ConfigKey::from_str(&format!("remote.{}.fetch", new))?;
+        let old_tracking = format!("refs/remotes/{}/", old);
+        let new_tracking = format!("refs/remotes/{}/", new);
+
+        let refspecs = file.get_all(&fetch);
+
+        if !refspecs.is_empty() {
+            file.unset(&fetch);
+
+            for refspec in refspecs {
+                file.add(&fetch, &refspec.replace(&old_tracking, &new_tracking));
+            }
+        }
+
+        // branches tracking the old remote
+        for (key, value) in file.entries() {
+            if key.section == "branch" && key.name == "remote" && value == old {
+                file.set(&key, new);
+            }
+        }
+
+        file.save()?;
+
+        rename_tracking_refs(&Repository::current()?, old, new)
+    }
+
+    /// The remote-tracking ref a remote ref is fetched into, if any.
+    pub fn tracking_ref(&self, remote_ref: &str) -> Option<String> {
+        self.fetch
+            .iter()
+            .find_map(|refspec| refspec.map(remote_ref))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_tracking_refs() {
+        let dir = std::env::temp_dir().join(format!("dgit-test-rename-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let (repository, _) = Repository::init(&dir, "main").unwrap();
+        let git_dir = repository.git_dir();
+        let hash = "b6fc4c620b67d95f953a5c1c1230aaab5db5a1b0";
+
+        repository
+            .update_ref_logged("refs/remotes/origin/main", hash, "fetch")
+            .unwrap();
+        std::fs::write(
+            git_dir.join("refs/remotes/origin/HEAD"),
+            "ref: refs/remotes/origin/main\n",
+        )
+        .unwrap();
+        std::fs::write(
+            git_dir.join("packed-refs"),
+            format!(
+                "# pack-refs with: peeled\n{0} refs/remotes/origin/v1\n^{0}\n{0} refs/remotes/other/main\n",
+                hash
+            ),
+        )
+        .unwrap();
+
+        rename_tracking_refs(&repository, "origin", "upstream").unwrap();
+
+        let refs = repository
+            .refs()
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<String>>();
+
+        assert!(!refs
+            .iter()
+            .any(|name| name.starts_with("refs/remotes/origin/")));
+        assert!(refs.contains(&"refs/remotes/other/main".to_string()));
+        assert_eq!(
+            repository
+                .resolve_ref("refs/remotes/upstream/main")
+                .unwrap(),
+            hash
+        );
+        assert_eq!(
+            repository.resolve_ref("refs/remotes/upstream/v1").unwrap(),
+            hash
+        );
+        assert_eq!(
+            repository
+                .resolve_ref("refs/remotes/upstream/HEAD")
+                .unwrap(),
+            hash
+        );
+        assert!(git_dir.join("logs/refs/remotes/upstream/main").is_file());
+        assert!(!git_dir.join("logs/refs/remotes/origin").exists());
+        assert!(std::fs::read_to_string(git_dir.join("packed-refs"))
+            .unwrap()
+            .contains(&format!("\n^{}\n", hash)));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
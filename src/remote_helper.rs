@@ -0,0 +1,647 @@
+use crate::commands::{
+    default_branch, download_objects, get_fetchable_refs, ref_value, upload_objects,
+};
+use crate::config::{Config, StorageBackend};
+use crate::contract_interaction::{ContractInteraction, Protection, Role};
+use crate::git::repository::Repository;
+use crate::git::revision::is_full_hash;
+use crate::git::walk::{is_ancestor, reachable_objects};
+use crate::remote::Remote;
+use anyhow::{anyhow, bail, Error, Result};
+use ethcontract::Address;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+/// A command git sends to a remote helper on stdin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HelperCommand {
+    Capabilities,
+    Option {
+        name: String,
+        value: String,
+    },
+    List {
+        for_push: bool,
+    },
+    Fetch {
+        hash: String,
+        name: String,
+    },
+    /// `push [+]<src>:<dst>`, an empty `src` deletes `dst`.
+    Push {
+        force: bool,
+        src: String,
+        dst: String,
+    },
+    /// A blank line, ending a batch of fetch or push commands, or the
+    /// session.
+    End,
+}
+
+impl FromStr for HelperCommand {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let line = s.trim_end_matches(['\r', '\n']);
+
+        if line.is_empty() {
+            return Ok(HelperCommand::End);
+        }
+
+        let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+
+        match command {
+            "capabilities" => Ok(HelperCommand::Capabilities),
+            "option" => {
+                let (name, value) = args.split_once(' ').unwrap_or((args, ""));
+
+                Ok(HelperCommand::Option {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                })
+            }
+            "list" => Ok(HelperCommand::List {
+                for_push: args == "for-push",
+            }),
+            "fetch" => {
+                let (hash, name) = args
+                    .split_once(' ')
+                    .ok_or_else(|| anyhow!("Malformed fetch command: {}", line))?;
+
+                Ok(HelperCommand::Fetch {
+                    hash: hash.to_string(),
+                    name: name.to_string(),
+                })
+            }
+            "push" => {
+                let (force, spec) = match args.strip_prefix('+') {
+                    Some(spec) => (true, spec),
+                    None => (false, args),
+                };
+
+                let (src, dst) = spec
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("Malformed push command: {}", line))?;
+
+                Ok(HelperCommand::Push {
+                    force,
+                    src: src.to_string(),
+                    dst: dst.to_string(),
+                })
+            }
+            _ => bail!("Unknown command: {}", line),
+        }
+    }
+}
+
+// what the helper needs from a remote: the refs on its contract and the
+// objects in its storage, faked in the tests
+trait Backend {
+    async fn refs(&self) -> Result<BTreeMap<String, Vec<u8>>>;
+
+    // Download the objects reachable from `tips` that `repository` lacks.
+    async fn download(&self, repository: &Repository, tips: &[String]) -> Result<usize>;
+
+    async fn require_writer(&self) -> Result<()>;
+
+    async fn sender(&self) -> Result<Address>;
+
+    async fn protection(&self, name: &str) -> Result<Option<Protection>>;
+
+    // Upload the objects of `repository` the remote doesn't have yet.
+    async fn upload(&self, repository: &Repository, objects: &[String]) -> Result<usize>;
+
+    // Set `name` from `old`, empty for a new ref, to `new`.
+    async fn update_ref(&self, name: &str, old: Vec<u8>, new: Vec<u8>, forced: bool) -> Result<()>;
+
+    // Delete `name`, which must still hold `old`.
+    async fn delete_ref(&self, name: &str, old: Vec<u8>) -> Result<()>;
+}
+
+struct ContractBackend {
+    contract: ContractInteraction,
+    storage: StorageBackend,
+}
+
+impl Backend for ContractBackend {
+    async fn refs(&self) -> Result<BTreeMap<String, Vec<u8>>> {
+        get_fetchable_refs(&self.contract).await
+    }
+
+    async fn download(&self, repository: &Repository, tips: &[String]) -> Result<usize> {
+        download_objects(&self.contract, repository, tips, |hash| {
+            eprintln!("Downloaded object {}", hash);
+        })
+        .await
+    }
+
+    async fn require_writer(&self) -> Result<()> {
+        Ok(self.contract.require_role(Role::Writer).await?)
+    }
+
+    async fn sender(&self) -> Result<Address> {
+        Ok(self.contract.sender_address().await?)
+    }
+
+    async fn protection(&self, name: &str) -> Result<Option<Protection>> {
+        Ok(self.contract.get_protection(name.to_string()).await?)
+    }
+
+    async fn upload(&self, repository: &Repository, objects: &[String]) -> Result<usize> {
+        let upload = upload_objects(&self.contract, self.storage, repository, objects, |hash| {
+            eprintln!("Uploaded object {}", hash);
+        })
+        .await?;
+
+        Ok(upload.count)
+    }
+
+    async fn update_ref(&self, name: &str, old: Vec<u8>, new: Vec<u8>, forced: bool) -> Result<()> {
+        if forced {
+            self.contract
+                .force_add_refs(vec![name.to_string()], vec![old], vec![new])
+                .await?;
+        } else {
+            self.contract.add_ref(name.to_string(), old, new).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete_ref(&self, name: &str, old: Vec<u8>) -> Result<()> {
+        self.contract
+            .delete_refs(vec![name.to_string()], vec![old])
+            .await?;
+
+        Ok(())
+    }
+}
+
+struct Helper<B: Backend> {
+    repository: Repository,
+    url: String,
+    backend: B,
+}
+
+impl<B: Backend> Helper<B> {
+    async fn list(&self, output: &mut impl Write) -> Result<()> {
+        let refs = self.backend.refs().await?;
+
+        for (name, data) in &refs {
+            writeln!(output, "{} {}", ref_value(data), name)?;
+        }
+
+        // the contract has no HEAD, point it at the default branch
+        if let Some(head) = default_branch(&refs) {
+            writeln!(output, "@{} HEAD", head)?;
+        }
+
+        writeln!(output)?;
+
+        Ok(())
+    }
+
+    async fn fetch(&self, hashes: &[String]) -> Result<()> {
+        let count = self.backend.download(&self.repository, hashes).await?;
+
+        eprintln!("Fetched {} objects from {}", count, self.url);
+
+        Ok(())
+    }
+
+    async fn delete_ref(&self, dst: &str, refs: &BTreeMap<String, Vec<u8>>) -> Result<()> {
+        let Some(old) = refs.get(dst) else {
+            bail!("remote ref does not exist");
+        };
+
+        if let Some(protection) = self.backend.protection(dst).await? {
+            protection.check_deletion(dst, self.backend.sender().await?)?;
+        }
+
+        self.backend.delete_ref(dst, old.clone()).await?;
+
+        eprintln!("Deleted {}", dst);
+
+        Ok(())
+    }
+
+    // `refs` are the refs of the remote, kept up to date as the pushes of a
+    // batch go through
+    async fn push_ref(
+        &self,
+        force: bool,
+        src: &str,
+        dst: &str,
+        refs: &BTreeMap<String, Vec<u8>>,
+    ) -> Result<()> {
+        self.backend.require_writer().await?;
+
+        // `:<dst>` deletes the remote ref
+        if src.is_empty() {
+            return self.delete_ref(dst, refs).await;
+        }
+
+        let new = if is_full_hash(src) {
+            src.to_lowercase()
+        } else {
+            self.repository.resolve_ref(src)?
+        };
+
+        if new.is_empty() {
+            bail!("src refspec {} does not match any", src);
+        }
+
+        let old_data = refs.get(dst).cloned().unwrap_or_default();
+        let mut forced = false;
+
+        if !old_data.is_empty() {
+            let old = ref_value(&old_data);
+
+            if old == new {
+                return Ok(());
+            }
+
+            forced =
+                !self.repository.has_object(&old) || !is_ancestor(&self.repository, &old, &new)?;
+
+            // the reasons git recognises in `error <dst> <why>`
+            if !force && !self.repository.has_object(&old) {
+                bail!("fetch first");
+            }
+
+            if !force && forced {
+                bail!("non-fast-forward");
+            }
+        }
+
+        if let Some(protection) = self.backend.protection(dst).await? {
+            protection.check_update(dst, self.backend.sender().await?, forced)?;
+        }
+
+        // the remote has its refs and their history, as far as they are
+        // present here, so only the objects past them are uploaded
+        let remote_tips = refs
+            .values()
+            .map(|data| ref_value(data))
+            .filter(|hash| self.repository.has_object(hash))
+            .collect::<Vec<String>>();
+
+        let remote_objects = reachable_objects(&self.repository, &remote_tips, &HashSet::new())?
+            .into_iter()
+            .collect::<HashSet<String>>();
+
+        let objects = reachable_objects(
+            &self.repository,
+            std::slice::from_ref(&new),
+            &remote_objects,
+        )?;
+
+        let count = self.backend.upload(&self.repository, &objects).await?;
+
+        self.backend
+            .update_ref(dst, old_data, new.clone().into_bytes(), forced)
+            .await?;
+
+        eprintln!("Pushed {} objects, {} -> {}", count, dst, new);
+
+        Ok(())
+    }
+
+    async fn push(&self, pushes: &[(bool, String, String)], output: &mut impl Write) -> Result<()> {
+        let mut refs = self.backend.refs().await?;
+
+        for (force, src, dst) in pushes {
+            match self.push_ref(*force, src, dst, &refs).await {
+                Ok(()) => {
+                    writeln!(output, "ok {}", dst)?;
+
+                    refs = self.backend.refs().await?;
+                }
+                Err(e) => writeln!(output, "error {} {}", dst, e)?,
+            }
+        }
+
+        writeln!(output)?;
+
+        Ok(())
+    }
+
+    // answer the commands read from `input` until it ends or a blank line
+    // comes outside of a batch
+    async fn serve(&self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        let mut fetches = Vec::new();
+        let mut pushes = Vec::new();
+
+        for line in input.lines() {
+            match HelperCommand::from_str(&line?)? {
+                HelperCommand::Capabilities => {
+                    writeln!(output, "fetch")?;
+                    writeln!(output, "push")?;
+                    writeln!(output)?;
+                }
+                HelperCommand::Option { .. } => writeln!(output, "unsupported")?,
+                HelperCommand::List { .. } => self.list(&mut output).await?,
+                HelperCommand::Fetch { hash, .. } => fetches.push(hash),
+                HelperCommand::Push { force, src, dst } => pushes.push((force, src, dst)),
+                HelperCommand::End if !fetches.is_empty() => {
+                    self.fetch(&fetches).await?;
+                    fetches.clear();
+
+                    writeln!(output)?;
+                }
+                HelperCommand::End if !pushes.is_empty() => {
+                    self.push(&pushes, &mut output).await?;
+                    pushes.clear();
+                }
+                HelperCommand::End => break,
+            }
+
+            output.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Serve the git remote helper protocol for `url` (`dgit::0x...` or
+/// `dgit://0x...`) until git closes `input` or sends a blank line outside
+/// of a batch. Only protocol responses are written to `output`, progress
+/// goes to stderr.
+pub async fn run(
+    remote_name: &str,
+    url: &str,
+    input: impl BufRead,
+    output: impl Write,
+) -> Result<()> {
+    let remote = Remote::from_url(remote_name, url)?;
+
+    let storage = match remote.storage {
+        Some(storage) => storage,
+        None => Config::storage_backend()?,
+    };
+
+    let helper = Helper {
+        repository: Repository::current()?,
+        url: remote.url(),
+        backend: ContractBackend {
+            contract: ContractInteraction::for_remote(&remote)?,
+            storage,
+        },
+    };
+
+    helper.serve(input, output).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_helper_command_from_str() {
+        assert_eq!(
+            HelperCommand::from_str("capabilities").unwrap(),
+            HelperCommand::Capabilities
+        );
+        assert_eq!(
+            HelperCommand::from_str("list for-push").unwrap(),
+            HelperCommand::List { for_push: true }
+        );
+        assert_eq!(
+            HelperCommand::from_str("option progress true").unwrap(),
+            HelperCommand::Option {
+                name: "progress".to_string(),
+                value: "true".to_string(),
+            }
+        );
+        assert_eq!(
+            HelperCommand::from_str(
+                "fetch 8c8e4a6bd3c7a1d9a1f1a8a2f6a4a3b0c0d5e1f2 refs/heads/main"
+            )
+            .unwrap(),
+            HelperCommand::Fetch {
+                hash: "8c8e4a6bd3c7a1d9a1f1a8a2f6a4a3b0c0d5e1f2".to_string(),
+                name: "refs/heads/main".to_string(),
+            }
+        );
+        assert_eq!(
+            HelperCommand::from_str("push +refs/heads/main:refs/heads/dev").unwrap(),
+            HelperCommand::Push {
+                force: true,
+                src: "refs/heads/main".to_string(),
+                dst: "refs/heads/dev".to_string(),
+            }
+        );
+        assert_eq!(
+            HelperCommand::from_str("push :refs/heads/old").unwrap(),
+            HelperCommand::Push {
+                force: false,
+                src: String::new(),
+                dst: "refs/heads/old".to_string(),
+            }
+        );
+        assert_eq!(HelperCommand::from_str("").unwrap(), HelperCommand::End);
+        assert!(HelperCommand::from_str("connect git-upload-pack").is_err());
+    }
+
+    // a remote kept in memory, with a repository standing in for its storage
+    struct FakeBackend {
+        refs: std::sync::Mutex<BTreeMap<String, Vec<u8>>>,
+        storage: Repository,
+        uploaded: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl Backend for FakeBackend {
+        async fn refs(&self) -> Result<BTreeMap<String, Vec<u8>>> {
+            Ok(self.refs.lock().unwrap().clone())
+        }
+
+        async fn download(&self, repository: &Repository, tips: &[String]) -> Result<usize> {
+            let mut queue = tips.to_vec();
+            let mut count = 0;
+
+            while let Some(hash) = queue.pop() {
+                if repository.has_object(&hash) {
+                    continue;
+                }
+
+                let data = self.storage.read_object(&hash)?;
+                repository.write_object(&data)?;
+                queue.extend(crate::git::walk::parse_children(data)?);
+                count += 1;
+            }
+
+            Ok(count)
+        }
+
+        async fn require_writer(&self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn sender(&self) -> Result<Address> {
+            Ok(Address::default())
+        }
+
+        async fn protection(&self, _name: &str) -> Result<Option<Protection>> {
+            Ok(None)
+        }
+
+        async fn upload(&self, repository: &Repository, objects: &[String]) -> Result<usize> {
+            for hash in objects {
+                self.storage.write_object(&repository.read_object(hash)?)?;
+            }
+
+            self.uploaded.lock().unwrap().extend_from_slice(objects);
+
+            Ok(objects.len())
+        }
+
+        async fn update_ref(
+            &self,
+            name: &str,
+            old: Vec<u8>,
+            new: Vec<u8>,
+            _forced: bool,
+        ) -> Result<()> {
+            let mut refs = self.refs.lock().unwrap();
+
+            // the compare-and-swap of the contract
+            if refs.get(name).cloned().unwrap_or_default() != old {
+                bail!("ref was updated since it was read");
+            }
+
+            refs.insert(name.to_string(), new);
+
+            Ok(())
+        }
+
+        async fn delete_ref(&self, name: &str, old: Vec<u8>) -> Result<()> {
+            let mut refs = self.refs.lock().unwrap();
+
+            if refs.get(name) != Some(&old) {
+                bail!("ref was updated since it was read");
+            }
+
+            refs.remove(name);
+
+            Ok(())
+        }
+    }
+
+    fn write_commit(repository: &Repository, files: &[(&str, &str)], parents: &[&str]) -> String {
+        let mut tree = Vec::new();
+
+        for (name, content) in files {
+            let blob = repository
+                .write_object(format!("blob {}\0{}", content.len(), content).as_bytes())
+                .unwrap();
+
+            tree.extend(format!("100644 {}\0", name).into_bytes());
+            tree.extend(hex::decode(blob).unwrap());
+        }
+
+        let mut data = format!("tree {}\0", tree.len()).into_bytes();
+        data.extend(tree);
+        let tree = repository.write_object(&data).unwrap();
+
+        let mut content = format!("tree {}\n", tree);
+
+        for parent in parents {
+            content.push_str(&format!("parent {}\n", parent));
+        }
+
+        content.push_str(
+            "author A U Thor <author@example.com> 1622519072 +0000\n\
+             committer A U Thor <author@example.com> 1622519072 +0000\n\nmessage\n",
+        );
+
+        repository
+            .write_object(format!("commit {}\0{}", content.len(), content).as_bytes())
+            .unwrap()
+    }
+
+    async fn serve(helper: &Helper<FakeBackend>, input: &str) -> String {
+        let mut output = Vec::new();
+
+        helper.serve(input.as_bytes(), &mut output).await.unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_helper_list_fetch_push() {
+        let dir = std::env::temp_dir().join(format!("dgit-test-helper-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let (local, _) = Repository::init(&dir.join("local"), "main").unwrap();
+        let helper = Helper {
+            repository: local.clone(),
+            url: "dgit::0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string(),
+            backend: FakeBackend {
+                refs: Default::default(),
+                storage: Repository::init_bare(&dir.join("storage"), "main").unwrap(),
+                uploaded: Default::default(),
+            },
+        };
+
+        let first = write_commit(&local, &[("a", "one")], &[]);
+        local.update_ref("refs/heads/main", &first).unwrap();
+
+        assert_eq!(
+            serve(&helper, "push refs/heads/main:refs/heads/main\n\n").await,
+            "ok refs/heads/main\n\n"
+        );
+        assert_eq!(helper.backend.uploaded.lock().unwrap().len(), 3);
+
+        // only the new commit, tree and blob: the blob `a` is already there
+        let second = write_commit(&local, &[("a", "one"), ("b", "two")], &[&first]);
+        local.update_ref("refs/heads/main", &second).unwrap();
+        helper.backend.uploaded.lock().unwrap().clear();
+
+        assert_eq!(
+            serve(&helper, "push refs/heads/main:refs/heads/main\n\n").await,
+            "ok refs/heads/main\n\n"
+        );
+        assert_eq!(helper.backend.uploaded.lock().unwrap().len(), 3);
+        assert!(!helper.backend.uploaded.lock().unwrap().contains(&first));
+
+        assert_eq!(
+            serve(&helper, "list\n").await,
+            format!("{} refs/heads/main\n@refs/heads/main HEAD\n\n", second)
+        );
+
+        let diverged = write_commit(&local, &[("c", "three")], &[&first]);
+        local.update_ref("refs/heads/other", &diverged).unwrap();
+
+        assert_eq!(
+            serve(&helper, "push refs/heads/other:refs/heads/main\n\n").await,
+            "error refs/heads/main non-fast-forward\n\n"
+        );
+        assert_eq!(
+            serve(&helper, "push +refs/heads/other:refs/heads/main\n\n").await,
+            "ok refs/heads/main\n\n"
+        );
+
+        let (clone, _) = Repository::init(&dir.join("clone"), "main").unwrap();
+        let fetcher = Helper {
+            repository: clone.clone(),
+            ..helper
+        };
+
+        assert_eq!(
+            serve(&fetcher, &format!("fetch {} refs/heads/main\n\n", diverged)).await,
+            "\n"
+        );
+        assert!(clone.has_object(&diverged));
+        assert!(clone.has_object(&first));
+        assert!(!clone.has_object(&second));
+
+        assert_eq!(
+            serve(&fetcher, "push :refs/heads/main\n\n").await,
+            "ok refs/heads/main\n\n"
+        );
+        assert_eq!(serve(&fetcher, "list\n").await, "\n");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,138 @@
+use crate::error::Error;
+use crate::signer::signer_key;
+use anyhow::{bail, Result};
+use ethcontract::web3::signing::{self, Key};
+use ethcontract::{Address, PrivateKey};
+
+const BEGIN: &str = "-----BEGIN ETHEREUM SIGNATURE-----";
+const END: &str = "-----END ETHEREUM SIGNATURE-----";
+
+/// The key commits are signed with, the one transactions are sent with.
+pub fn signing_key() -> Result<PrivateKey> {
+    signer_key()?.ok_or_else(|| {
+        Error::Config("No signing key configured, set dgit.keystore, MNEMONIC or PK".to_string())
+            .into()
+    })
+}
+
+// r || s || v as hex between the armor lines, like a gpg signature in a
+// `gpgsig` header
+fn armor(signature: &[u8]) -> String {
+    format!("{}\n{}\n{}", BEGIN, hex::encode(signature), END)
+}
+
+fn dearmor(armored: &str) -> Result<Vec<u8>> {
+    let mut lines = armored.lines().map(str::trim);
+
+    if lines.next() != Some(BEGIN) {
+        bail!("Not an Ethereum signature");
+    }
+
+    let body = lines
+        .by_ref()
+        .take_while(|line| *line != END)
+        .collect::<String>();
+
+    let signature = hex::decode(body.trim_start_matches("0x"))?;
+
+    if signature.len() != 65 {
+        bail!("Invalid signature length: {}", signature.len());
+    }
+
+    Ok(signature)
+}
+
+/// Sign `payload` as an EIP-191 personal message, armored for a `gpgsig`
+/// header.
+pub fn sign(payload: &[u8], key: &PrivateKey) -> Result<String> {
+    let hash = signing::hash_message(payload);
+
+    let signature = key
+        .sign_message(hash.as_bytes())
+        .map_err(|e| Error::Chain(e.to_string().into()))?;
+
+    let mut bytes = Vec::with_capacity(65);
+    bytes.extend_from_slice(signature.r.as_bytes());
+    bytes.extend_from_slice(signature.s.as_bytes());
+    bytes.push(signature.v as u8 + 27);
+
+    Ok(armor(&bytes))
+}
+
+/// Recover the address that made the armored `signature` over `payload`.
+pub fn recover_signer(payload: &[u8], signature: &str) -> Result<Address> {
+    let signature = dearmor(signature)?;
+    let hash = signing::hash_message(payload);
+
+    // both the raw recovery id and the `personal_sign` one (27 or 28)
+    let recovery_id = match signature[64] {
+        v @ 0..=1 => v,
+        v @ 27..=28 => v - 27,
+        v => bail!("Invalid signature recovery id: {}", v),
+    };
+
+    signing::recover(hash.as_bytes(), &signature[..64], recovery_id as i32)
+        .map_err(|e| anyhow::anyhow!("Invalid signature: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the first account of the default Hardhat and Anvil mnemonic
+    const TEST_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const TEST_ADDRESS: &str = "f39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+    const PAYLOAD: &[u8] = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+        author A U Thor <author@example.com> 1700000000 +0000\n\
+        committer A U Thor <author@example.com> 1700000000 +0000\n\
+        \n\
+        signed commit\n";
+
+    fn test_key() -> PrivateKey {
+        PrivateKey::from_slice(hex::decode(TEST_KEY).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_recover() {
+        let signature = sign(PAYLOAD, &test_key()).unwrap();
+        let signer = recover_signer(PAYLOAD, &signature).unwrap();
+
+        assert_eq!(hex::encode(signer.to_fixed_bytes()), TEST_ADDRESS);
+    }
+
+    #[test]
+    fn test_tampered_commit() {
+        let signature = sign(PAYLOAD, &test_key()).unwrap();
+        let signer = test_key().public_address();
+
+        // another message recovers some other address, if any
+        let tampered = String::from_utf8_lossy(PAYLOAD).replace("signed", "forged");
+
+        assert!(recover_signer(tampered.as_bytes(), &signature).map_or(true, |a| a != signer));
+
+        // and so does another signature
+        let mut bytes = dearmor(&signature).unwrap();
+        bytes[10] ^= 0xff;
+
+        assert!(recover_signer(PAYLOAD, &armor(&bytes)).map_or(true, |a| a != signer));
+
+        bytes[64] = 5;
+
+        assert!(recover_signer(PAYLOAD, &armor(&bytes)).is_err());
+    }
+
+    #[test]
+    fn test_signature_armor() {
+        let signature = (0..65).collect::<Vec<u8>>();
+
+        let armored = armor(&signature);
+
+        assert!(armored.starts_with(BEGIN));
+        assert!(armored.ends_with(END));
+        assert_eq!(dearmor(&armored).unwrap(), signature);
+
+        assert!(dearmor("-----BEGIN PGP SIGNATURE-----\nabcd").is_err());
+        assert!(dearmor(&armor(&[0; 64])).is_err());
+    }
+}
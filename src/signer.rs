@@ -0,0 +1,258 @@
+use crate::config::Config;
+use crate::contract_interaction::address_to_string;
+use crate::error::{Error, Result};
+use coins_bip39::{English, Mnemonic};
+use ethcontract::PrivateKey;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Where the key transactions and commit signatures are made with comes
+/// from, in the order they are looked for.
+#[derive(Clone, PartialEq)]
+pub enum SignerSource {
+    /// An encrypted JSON keystore, `dgit.keystore`.
+    Keystore(PathBuf),
+    /// A BIP-39 mnemonic, `MNEMONIC`, and the path to derive the key at.
+    Mnemonic { phrase: String, path: String },
+    /// A raw hex private key, `PK`.
+    RawKey(String),
+    /// No key: transactions are sent from the node's unlocked account.
+    Node,
+}
+
+impl SignerSource {
+    pub fn configured() -> Result<Self> {
+        if let Some(keystore) = Config::keystore()? {
+            return Ok(SignerSource::Keystore(find_keystore(&keystore)?));
+        }
+
+        if let Some(phrase) = Config::mnemonic() {
+            return Ok(SignerSource::Mnemonic {
+                phrase,
+                path: Config::derivation_path()?,
+            });
+        }
+
+        match Config::pk() {
+            Ok(pk) => Ok(SignerSource::RawKey(pk)),
+            Err(_) => Ok(SignerSource::Node),
+        }
+    }
+
+    pub fn load_key(&self) -> Result<Option<PrivateKey>> {
+        match self {
+            SignerSource::Keystore(path) => {
+                let password = password(&format!("Password for {}: ", path.display()))?;
+
+                decrypt_keystore(path, &password).map(Some)
+            }
+            SignerSource::Mnemonic { phrase, path } => key_from_mnemonic(phrase, path).map(Some),
+            SignerSource::RawKey(pk) => parse_private_key(pk).map(Some),
+            SignerSource::Node => Ok(None),
+        }
+    }
+}
+
+/// The key transactions and commit signatures are made with, loaded once
+/// per run. `None` when the node's account sends transactions.
+pub fn signer_key() -> Result<Option<PrivateKey>> {
+    static KEY: OnceLock<Option<PrivateKey>> = OnceLock::new();
+
+    if let Some(key) = KEY.get() {
+        return Ok(key.clone());
+    }
+
+    let key = SignerSource::configured()?.load_key()?;
+
+    Ok(KEY.get_or_init(|| key).clone())
+}
+
+/// `DGIT_PASSWORD`, or the password typed at `prompt`.
+pub fn password(prompt: &str) -> Result<String> {
+    match Config::keystore_password() {
+        Some(password) => Ok(password),
+        None => Ok(rpassword::prompt_password(prompt)?),
+    }
+}
+
+pub fn parse_private_key(pk: &str) -> Result<PrivateKey> {
+    let bytes = hex::decode(pk.trim().trim_start_matches("0x"))
+        .map_err(|_| Error::Config("Private key is not hex".to_string()))?;
+
+    PrivateKey::from_slice(bytes).map_err(|e| Error::Config(format!("Invalid private key: {}", e)))
+}
+
+/// Derive the key at `path` (like `m/44'/60'/0'/0/0`) from a BIP-39
+/// mnemonic.
+pub fn key_from_mnemonic(phrase: &str, path: &str) -> Result<PrivateKey> {
+    let mnemonic = Mnemonic::<English>::new_from_phrase(phrase.trim())
+        .map_err(|e| Error::Config(format!("Invalid mnemonic: {}", e)))?;
+
+    let key = mnemonic
+        .derive_key(path, None)
+        .map_err(|e| Error::Config(format!("Invalid derivation path {}: {}", path, e)))?;
+
+    let key: &coins_bip32::ecdsa::SigningKey = key.as_ref();
+
+    PrivateKey::from_slice(key.to_bytes())
+        .map_err(|e| Error::Config(format!("Invalid derived key: {}", e)))
+}
+
+pub fn decrypt_keystore(path: &Path, password: &str) -> Result<PrivateKey> {
+    let bytes = eth_keystore::decrypt_key(path, password)
+        .map_err(|e| Error::Config(format!("Could not decrypt {}: {}", path.display(), e)))?;
+
+    PrivateKey::from_slice(bytes)
+        .map_err(|e| Error::Config(format!("Invalid key in {}: {}", path.display(), e)))
+}
+
+/// Encrypt `key` into a keystore named after its address in the keystore
+/// directory.
+pub fn encrypt_keystore(key: &PrivateKey, password: &str) -> Result<PathBuf> {
+    let dir = Config::keystore_dir()?;
+    let address = address_to_string(&key.public_address());
+
+    std::fs::create_dir_all(&dir)?;
+
+    eth_keystore::encrypt_key(
+        &dir,
+        &mut rand::thread_rng(),
+        key.secret_bytes(),
+        password,
+        Some(&address),
+    )
+    .map_err(|e| Error::Config(format!("Could not write keystore: {}", e)))?;
+
+    Ok(dir.join(address))
+}
+
+// geth names keystores `UTC--<date>--<address>` and stores the address
+// without `0x`, dgit names them after the address
+fn keystore_address(path: &Path) -> Option<String> {
+    let from_json = std::fs::read(path)
+        .ok()
+        .and_then(|data| serde_json::from_slice::<serde_json::Value>(&data).ok())
+        .and_then(|json| json.get("address")?.as_str().map(str::to_string));
+
+    let from_name = path
+        .file_name()?
+        .to_str()?
+        .rsplit("--")
+        .next()
+        .map(str::to_string);
+
+    let address = from_json.or(from_name)?.to_lowercase();
+    let address = address.trim_start_matches("0x");
+
+    if address.len() == 40 && address.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(format!("0x{}", address))
+    } else {
+        None
+    }
+}
+
+/// The keystores of the keystore directory, by address.
+pub fn list_keystores() -> Result<Vec<(String, PathBuf)>> {
+    let dir = Config::keystore_dir()?;
+
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut keystores = Vec::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if let Some(address) = keystore_address(&path) {
+            keystores.push((address, path));
+        }
+    }
+
+    keystores.sort();
+
+    Ok(keystores)
+}
+
+// a keystore file, or the address of one in the keystore directory
+fn find_keystore(keystore: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(keystore);
+
+    if path.is_file() {
+        return Ok(path);
+    }
+
+    let address = keystore.to_lowercase();
+
+    list_keystores()?
+        .into_iter()
+        .find(|(candidate, _)| *candidate == address)
+        .map(|(_, path)| path)
+        .ok_or_else(|| Error::Config(format!("No keystore found for {}", keystore)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the first account of the default Hardhat and Anvil mnemonic
+    const TEST_ADDRESS: &str = "f39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+    fn address_hex(key: &PrivateKey) -> String {
+        hex::encode(key.public_address().to_fixed_bytes())
+    }
+
+    #[test]
+    fn test_key_from_mnemonic() {
+        let key = key_from_mnemonic(
+            "test test test test test test test test test test test junk",
+            "m/44'/60'/0'/0/0",
+        )
+        .unwrap();
+
+        assert_eq!(address_hex(&key), TEST_ADDRESS);
+        assert!(key_from_mnemonic("not a mnemonic", "m/44'/60'/0'/0/0").is_err());
+    }
+
+    #[test]
+    fn test_parse_private_key() {
+        let key = parse_private_key(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80\n",
+        )
+        .unwrap();
+
+        assert_eq!(address_hex(&key), TEST_ADDRESS);
+        assert!(parse_private_key("0xnothex").is_err());
+    }
+
+    #[test]
+    fn test_keystore_address() {
+        let dir = std::env::temp_dir().join(format!("dgit-keystore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let geth =
+            dir.join("UTC--2024-01-01T00-00-00.0Z--8ba1f109551bd432803012645ac136ddd64dba72");
+        std::fs::write(&geth, "{}").unwrap();
+        assert_eq!(
+            keystore_address(&geth),
+            Some("0x8ba1f109551bd432803012645ac136ddd64dba72".to_string())
+        );
+
+        let named = dir.join("key.json");
+        std::fs::write(
+            &named,
+            r#"{"address":"8BA1F109551BD432803012645AC136DDD64DBA72"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            keystore_address(&named),
+            Some("0x8ba1f109551bd432803012645ac136ddd64dba72".to_string())
+        );
+
+        let other = dir.join("notes.txt");
+        std::fs::write(&other, "hello").unwrap();
+        assert_eq!(keystore_address(&other), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
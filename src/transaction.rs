@@ -0,0 +1,461 @@
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::git::repository::Repository;
+use ethcontract::dyns::DynTransport;
+use ethcontract::prelude::*;
+use ethcontract::transaction::{ResolveCondition, TransactionBuilder};
+use ethcontract::web3::types::{BlockNumber, TransactionReceipt};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a sent transaction cost, from its receipt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionCost {
+    pub hash: String,
+    pub gas_used: U256,
+    /// The fee paid in wei, the gas used at the effective gas price.
+    pub fee: U256,
+}
+
+impl From<&TransactionReceipt> for TransactionCost {
+    fn from(receipt: &TransactionReceipt) -> Self {
+        let gas_used = receipt.gas_used.unwrap_or_default();
+        let gas_price = receipt.effective_gas_price.unwrap_or_default();
+
+        TransactionCost {
+            hash: format!("{:?}", receipt.transaction_hash),
+            gas_used,
+            fee: gas_used * gas_price,
+        }
+    }
+}
+
+/// How transactions are sent and confirmed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionPolicy {
+    /// How many blocks a transaction must be in, the one it was mined in
+    /// included.
+    pub confirmations: usize,
+    /// How long to wait for a transaction to be mined before replacing it.
+    pub timeout: Duration,
+    /// The percentage the gas price is raised by for a replacement.
+    pub fee_bump: u64,
+    /// How many times a transaction is sent, replacements included.
+    pub attempts: usize,
+}
+
+impl TransactionPolicy {
+    pub fn load() -> Result<Self> {
+        Ok(TransactionPolicy {
+            confirmations: Config::confirmations()?,
+            timeout: Duration::from_secs(Config::transaction_timeout()?),
+            fee_bump: Config::fee_bump()?,
+            attempts: Config::transaction_attempts()?.max(1),
+        })
+    }
+}
+
+/// The gas price of a replacement: nodes only accept one priced strictly
+/// higher, by at least 10% for geth.
+pub fn bump_fee(gas_price: U256, percent: u64) -> U256 {
+    gas_price * U256::from(100 + percent) / U256::from(100u64) + U256::from(1u64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SendFailure {
+    /// The gas price is too low to be accepted or to replace the
+    /// transaction with the same nonce.
+    Underpriced,
+    /// The same transaction is already in the pool.
+    AlreadyKnown,
+    /// The nonce was used, possibly by an earlier attempt being mined.
+    NonceTooLow,
+    Other,
+}
+
+// nodes only report these as messages, geth and hardhat word them alike
+fn classify(message: &str) -> SendFailure {
+    let message = message.to_lowercase();
+
+    if message.contains("underpriced") || message.contains("fee too low") {
+        SendFailure::Underpriced
+    } else if message.contains("already known") || message.contains("already imported") {
+        SendFailure::AlreadyKnown
+    } else if message.contains("nonce too low") {
+        SendFailure::NonceTooLow
+    } else {
+        SendFailure::Other
+    }
+}
+
+/// A transaction sent but not confirmed yet. The replacements of a stuck
+/// transaction share its sender and nonce.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InFlight {
+    pub hash: H256,
+    pub from: Address,
+    pub nonce: u64,
+}
+
+impl InFlight {
+    // `<hash> <from> <nonce>`
+    fn to_line(&self) -> String {
+        format!("{:?} {:?} {}\n", self.hash, self.from, self.nonce)
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+
+        Some(InFlight {
+            hash: parts.next()?.parse().ok()?,
+            from: parts.next()?.parse().ok()?,
+            nonce: parts.next()?.parse().ok()?,
+        })
+    }
+
+    fn replaces(&self, other: &InFlight) -> bool {
+        self.from == other.from && self.nonce == other.nonce
+    }
+}
+
+// `.git/dgit/transactions`, the transactions sent but not yet confirmed,
+// so a run that was interrupted can wait for them
+fn in_flight_path() -> Option<PathBuf> {
+    Repository::current()
+        .ok()
+        .map(|repository| repository.git_dir().join("dgit").join("transactions"))
+}
+
+/// The transactions sent but not confirmed yet, left by an interrupted run
+/// or still being waited for.
+pub fn read_in_flight() -> Result<Vec<InFlight>> {
+    let Some(path) = in_flight_path().filter(|path| path.is_file()) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .filter_map(InFlight::from_line)
+        .collect())
+}
+
+fn write_in_flight(in_flight: &[InFlight]) -> Result<()> {
+    let Some(path) = in_flight_path() else {
+        return Ok(());
+    };
+
+    if in_flight.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let lines = in_flight.iter().map(InFlight::to_line).collect::<String>();
+
+    Ok(std::fs::write(path, lines)?)
+}
+
+/// Sends transactions one nonce after the other, waits for them to be
+/// confirmed and replaces the ones that are stuck with a higher fee.
+pub struct TransactionManager {
+    client: Web3<Http>,
+    policy: TransactionPolicy,
+    // the next nonce to use, ahead of the node's pending count while our
+    // own transactions propagate
+    next_nonce: Mutex<Option<U256>>,
+    resumed: Mutex<bool>,
+}
+
+impl TransactionManager {
+    pub fn new(client: Web3<Http>) -> Result<Self> {
+        Ok(TransactionManager {
+            client,
+            policy: TransactionPolicy::load()?,
+            next_nonce: Mutex::new(None),
+            resumed: Mutex::new(false),
+        })
+    }
+
+    async fn nonce(&self, from: Address) -> Result<U256> {
+        let pending = self
+            .client
+            .eth()
+            .transaction_count(from, Some(BlockNumber::Pending))
+            .await
+            .map_err(Error::chain)?;
+
+        let mut next_nonce = self.next_nonce.lock().await;
+
+        let nonce = match *next_nonce {
+            Some(next) if next > pending => next,
+            _ => pending,
+        };
+
+        *next_nonce = Some(nonce + U256::from(1u64));
+
+        Ok(nonce)
+    }
+
+    // the first receipt of any of `hashes`, which all share a nonce so at
+    // most one of them is mined
+    async fn wait_for_receipt(
+        &self,
+        hashes: &[H256],
+        timeout: Duration,
+    ) -> Result<Option<TransactionReceipt>> {
+        let start = Instant::now();
+
+        loop {
+            for hash in hashes {
+                let receipt = self
+                    .client
+                    .eth()
+                    .transaction_receipt(*hash)
+                    .await
+                    .map_err(Error::chain)?;
+
+                if let Some(receipt) = receipt.filter(|receipt| receipt.block_number.is_some()) {
+                    return Ok(Some(receipt));
+                }
+            }
+
+            if start.elapsed() >= timeout {
+                return Ok(None);
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn confirm(&self, receipt: TransactionReceipt) -> Result<TransactionCost> {
+        if receipt.status.is_some_and(|status| status.is_zero()) {
+            return Err(Error::Chain(
+                format!("Transaction {:?} reverted", receipt.transaction_hash).into(),
+            ));
+        }
+
+        let mined = receipt.block_number.unwrap_or_default().as_u64();
+        let confirmed = mined + self.policy.confirmations.max(1) as u64 - 1;
+
+        while self
+            .client
+            .eth()
+            .block_number()
+            .await
+            .map_err(Error::chain)?
+            .as_u64()
+            < confirmed
+        {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+
+        Ok(TransactionCost::from(&receipt))
+    }
+
+    // wait for the transactions an interrupted run left in flight before
+    // sending new ones, so they aren't sent twice
+    async fn resume(&self) -> Result<()> {
+        let mut resumed = self.resumed.lock().await;
+
+        if *resumed {
+            return Ok(());
+        }
+
+        *resumed = true;
+
+        let mut in_flight = read_in_flight()?;
+
+        // a nonce that was mined, by the transaction or a replacement of it,
+        // has nothing left to wait for
+        let mut mined = HashMap::new();
+
+        for entry in &in_flight {
+            if let Entry::Vacant(slot) = mined.entry(entry.from) {
+                let count = self
+                    .client
+                    .eth()
+                    .transaction_count(entry.from, Some(BlockNumber::Latest))
+                    .await
+                    .map_err(Error::chain)?;
+
+                slot.insert(count.as_u64());
+            }
+        }
+
+        in_flight.retain(|entry| entry.nonce >= mined[&entry.from]);
+
+        for entry in in_flight.clone() {
+            if !in_flight.contains(&entry) {
+                continue;
+            }
+
+            let hashes = in_flight
+                .iter()
+                .filter(|other| other.replaces(&entry))
+                .map(|other| other.hash)
+                .collect::<Vec<_>>();
+
+            eprintln!(
+                "Waiting for in-flight transaction {:?} (nonce {})",
+                entry.hash, entry.nonce
+            );
+
+            if let Some(receipt) = self.wait_for_receipt(&hashes, self.policy.timeout).await? {
+                // a reverted leftover doesn't fail the new run
+                self.confirm(receipt).await.ok();
+                in_flight.retain(|other| !other.replaces(&entry));
+            }
+        }
+
+        write_in_flight(&in_flight)
+    }
+
+    /// Send `transaction` from `from` and wait until it is confirmed,
+    /// replacing it with a higher fee whenever it isn't mined in time.
+    pub async fn send(
+        &self,
+        from: Address,
+        transaction: TransactionBuilder<DynTransport>,
+    ) -> Result<TransactionCost> {
+        self.resume().await?;
+
+        let nonce = self.nonce(from).await?;
+
+        let mut gas_price = self.client.eth().gas_price().await.map_err(Error::chain)?;
+        let mut hashes = Vec::new();
+        let mut underpriced = None;
+
+        for attempt in 0..self.policy.attempts {
+            if attempt > 0 {
+                gas_price = bump_fee(gas_price, self.policy.fee_bump);
+            }
+
+            let sent = transaction
+                .clone()
+                .nonce(nonce)
+                .gas_price(GasPrice::Legacy(gas_price))
+                .resolve(ResolveCondition::Pending)
+                .send()
+                .await;
+
+            match sent {
+                Ok(result) => {
+                    hashes.push(result.hash());
+
+                    let mut in_flight = read_in_flight()?;
+                    in_flight.push(InFlight {
+                        hash: result.hash(),
+                        from,
+                        nonce: nonce.as_u64(),
+                    });
+                    write_in_flight(&in_flight)?;
+                }
+                Err(e) => match classify(&e.to_string()) {
+                    SendFailure::Underpriced => {
+                        underpriced = Some(e);
+                        continue;
+                    }
+                    SendFailure::AlreadyKnown => {}
+                    SendFailure::NonceTooLow if !hashes.is_empty() => {}
+                    _ => {
+                        // the nonce was never used
+                        if hashes.is_empty() {
+                            *self.next_nonce.lock().await = None;
+                        }
+
+                        return Err(Error::chain(e));
+                    }
+                },
+            }
+
+            if let Some(receipt) = self.wait_for_receipt(&hashes, self.policy.timeout).await? {
+                let mut in_flight = read_in_flight()?;
+                in_flight.retain(|entry| entry.from != from || entry.nonce != nonce.as_u64());
+                write_in_flight(&in_flight)?;
+
+                return self.confirm(receipt).await;
+            }
+        }
+
+        // no attempt was accepted, so there is nothing to wait for and the
+        // nonce is still free
+        if hashes.is_empty() {
+            *self.next_nonce.lock().await = None;
+
+            if let Some(e) = underpriced {
+                return Err(Error::chain(e));
+            }
+        }
+
+        Err(Error::Chain(
+            format!(
+                "Transaction with nonce {} was not mined after {} attempts, run the command again to wait for it",
+                nonce, self.policy.attempts
+            )
+            .into(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_bump_fee() {
+        assert_eq!(bump_fee(U256::from(100u64), 15), U256::from(116u64));
+        assert_eq!(
+            bump_fee(U256::from(1_000_000_000u64), 10),
+            U256::from(1_100_000_001u64)
+        );
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify("replacement transaction underpriced"),
+            SendFailure::Underpriced
+        );
+        assert_eq!(classify("already known"), SendFailure::AlreadyKnown);
+        assert_eq!(
+            classify("Nonce too low. Expected nonce to be 5 but got 4."),
+            SendFailure::NonceTooLow
+        );
+        assert_eq!(classify("execution reverted"), SendFailure::Other);
+    }
+
+    #[test]
+    fn test_in_flight_line() {
+        let entry = InFlight {
+            hash: H256::from_str(
+                "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060",
+            )
+            .unwrap(),
+            from: Address::from_str("0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266").unwrap(),
+            nonce: 7,
+        };
+
+        assert_eq!(
+            entry.to_line(),
+            "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060 \
+             0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266 7\n"
+        );
+        assert_eq!(InFlight::from_line(&entry.to_line()), Some(entry.clone()));
+        assert!(entry.replaces(&InFlight {
+            hash: H256::default(),
+            ..entry.clone()
+        }));
+    }
+}
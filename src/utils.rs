@@ -1,4 +1,5 @@
 use anyhow::Result;
+use ethcontract::U256;
 use std::path::Path;
 use walkdir::WalkDir;
 
@@ -21,7 +22,7 @@ pub async fn get_object_hashes(git_objects_path: &str) -> Result<(Vec<String>, V
                 {
                     let hash = relative_path.replace('/', "");
                     object_hashes.push(hash);
-                    object_paths.push(path.to_str().unwrap().to_string());
+                    object_paths.push(path.to_string_lossy().to_string());
                 }
             }
         }
@@ -54,3 +55,32 @@ pub fn get_refs(git_refs_path: &str) -> Result<Vec<String>> {
 
     Ok(refs)
 }
+
+/// Format an amount of wei as ether, like `0.0021`.
+pub fn format_ether(wei: U256) -> String {
+    let unit = U256::exp10(18);
+    let fraction = format!("{:018}", (wei % unit).as_u64());
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        format!("{}", wei / unit)
+    } else {
+        format!("{}.{}", wei / unit, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_ether() {
+        assert_eq!(format_ether(U256::zero()), "0");
+        assert_eq!(format_ether(U256::exp10(18)), "1");
+        assert_eq!(format_ether(U256::from(2_100_000_000_000_000u64)), "0.0021");
+        assert_eq!(
+            format_ether(U256::from(1_500_000_000_000_000_001u64)),
+            "1.500000000000000001"
+        );
+    }
+}